@@ -0,0 +1,314 @@
+//! Runs the games in a terminal instead of on the board.
+//!
+//! The number keys stand in for the button matrix in numpad layout, so `7`
+//! is the top left cell and `3` the bottom right. As over the board's serial
+//! port, `u` takes back the last move and `r` starts a new game. `q` quits.
+//!
+//! Chords can't be held on a keyboard, so `s` holds the top left and bottom
+//! right corners together to open the brightness settings, and `e` holds all
+//! four corners to end the game. Settings are kept in `ttt-sim.eeprom` in the
+//! temporary directory.
+//!
+//! The computer plays blue, as on the board.
+//!
+//! The input of each game is printed below the board once it ends. Passing it
+//! after the game's name plays that game back exactly, before handing over to
+//! the keyboard.
+//!
+//! Run from this directory with `cargo run`, or `cargo run -- hexapawn`. The
+//! library's tests run from here too, with `cargo test -p ttt --lib`.
+
+use std::{
+    array::from_fn,
+    env, fs,
+    io::{stdin, stdout, Read, Write},
+    path::PathBuf,
+    process::{exit, Command, Stdio},
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ttt::{
+    board::Board,
+    display::DisplayPixel,
+    hexapawn::Hexapawn,
+    io::{Clock, Event, FrameSink, InputSource, Storage},
+    player::Player,
+    position::{pos, Position},
+    recording::Recording,
+    runtime::Runtime,
+    scan::ButtonScan,
+    time,
+    ttt::TicTacToe,
+};
+
+const WIDTH: usize = 3;
+const HEIGHT: usize = 3;
+/// The side the computer plays, as on the board. Recordings only play back
+/// the same way with the same setting.
+const COMPUTER: Option<Player> = Some(Player::Blue);
+
+/// How long a key press keeps its simulated button held down.
+const HOLD: Duration = Duration::from_millis(150);
+/// How long a chord key keeps its buttons held down, long enough to count.
+const CHORD_HOLD: Duration = Duration::from_millis(1500);
+const DIAGONAL: [Position; 2] = [pos(0, 0), pos(WIDTH - 1, HEIGHT - 1)];
+const CORNERS: [Position; 4] = [
+    pos(0, 0),
+    pos(WIDTH - 1, 0),
+    pos(0, HEIGHT - 1),
+    pos(WIDTH - 1, HEIGHT - 1),
+];
+/// Time between redraws, so the terminal isn't flooded.
+const FRAME: Duration = Duration::from_millis(20);
+
+struct Terminal {
+    buffer: Board<DisplayPixel, WIDTH, HEIGHT>,
+    brightness: u8,
+    drawn_at: Option<Instant>,
+}
+
+impl Terminal {
+    fn new() -> Self {
+        Self {
+            buffer: [[DisplayPixel::default(); WIDTH]; HEIGHT],
+            brightness: DisplayPixel::MAX,
+            drawn_at: None,
+        }
+    }
+
+    fn draw(&self) {
+        let mut frame = String::from("\x1b[H");
+        for row in &self.buffer {
+            for pixel in row {
+                if *pixel == DisplayPixel::default() {
+                    frame.push_str("\x1b[90m");
+                } else {
+                    let [red, blue] = [pixel.red_level(), pixel.blue_level()]
+                        .map(|level| u32::from(level) * 255 / u32::from(DisplayPixel::MAX));
+                    frame.push_str(&format!("\x1b[38;2;{red};0;{blue}m"));
+                }
+                frame.push_str("██ ");
+            }
+            frame.push_str("\x1b[0m\r\n");
+        }
+        frame.push_str(
+            "\r\n1-9: press   u: undo   r: reset   s: settings   e: end game   q: quit\r\n",
+        );
+        let mut stdout = stdout();
+        stdout.write_all(frame.as_bytes()).unwrap();
+        stdout.flush().unwrap();
+    }
+}
+
+impl FrameSink<WIDTH, HEIGHT> for Terminal {
+    fn write(&mut self, buffer: Board<DisplayPixel, WIDTH, HEIGHT>) {
+        self.buffer = buffer.map(|row| row.map(|pixel| pixel.scale(self.brightness)));
+    }
+
+    fn show(&mut self) {
+        if self
+            .drawn_at
+            .is_none_or(|drawn_at| drawn_at.elapsed() >= FRAME)
+        {
+            self.draw();
+            self.drawn_at = Some(Instant::now());
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        self.brightness = level;
+    }
+}
+
+struct Keyboard {
+    keys: Receiver<u8>,
+    /// The buttons held down, since when, and for how long.
+    held: Option<(Board<bool, WIDTH, HEIGHT>, Instant, Duration)>,
+    /// An undo or reset typed and not yet handed out.
+    command: Option<Event>,
+    saved_mode: String,
+}
+
+impl Keyboard {
+    /// Puts the terminal into unbuffered, silent mode and starts reading keys.
+    fn new() -> Self {
+        let saved_mode = stty(&["-g"]);
+        stty(&["-icanon", "-echo", "-isig"]);
+        print!("\x1b[2J\x1b[?25l");
+
+        let (sender, keys) = channel();
+        thread::spawn(move || {
+            for key in stdin().lock().bytes() {
+                if sender.send(key.unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            keys,
+            held: None,
+            command: None,
+            saved_mode,
+        }
+    }
+
+    fn key(&mut self, key: u8) {
+        let (positions, hold) = match key {
+            b'1'..=b'9' => {
+                let index = usize::from(key - b'1');
+                (&[pos(index % WIDTH, HEIGHT - 1 - index / WIDTH)][..], HOLD)
+            }
+            b's' => (&DIAGONAL[..], CHORD_HOLD),
+            b'e' => (&CORNERS[..], CHORD_HOLD),
+            b'u' | b'r' => {
+                self.command = Some(if key == b'u' {
+                    Event::Undo
+                } else {
+                    Event::Reset
+                });
+                return;
+            }
+            // Ctrl-C arrives as a byte, since signals are off.
+            b'q' | 3 => self.quit(),
+            _ => return,
+        };
+        let buttons = from_fn(|y| from_fn(|x| positions.contains(&pos(x, y))));
+        self.held = Some((buttons, Instant::now(), hold));
+    }
+
+    fn quit(&self) -> ! {
+        stty(&[self.saved_mode.trim()]);
+        print!("\x1b[?25h");
+        stdout().flush().unwrap();
+        exit(0);
+    }
+}
+
+impl InputSource<WIDTH, HEIGHT> for Keyboard {
+    fn read(&mut self) -> ButtonScan<WIDTH, HEIGHT> {
+        while let Ok(key) = self.keys.try_recv() {
+            self.key(key);
+        }
+        ButtonScan::new(
+            self.held
+                .filter(|(_, since, hold)| since.elapsed() < *hold)
+                .map_or([[false; WIDTH]; HEIGHT], |(buttons, ..)| buttons),
+        )
+    }
+
+    fn sleep(&mut self) {
+        if let Ok(key) = self.keys.recv() {
+            self.key(key);
+        }
+    }
+
+    fn event(&mut self) -> Option<Event> {
+        self.command.take()
+    }
+}
+
+struct SimClock(Instant);
+
+impl Clock for SimClock {
+    fn now(&self) -> time::Instant {
+        time::Instant::from_millis(self.0.elapsed().as_millis() as u32)
+    }
+    /// Gives the CPU back until the next millisecond.
+    fn wait(&self) {
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Stands in for the EEPROM with a file in the temporary directory, so
+/// settings outlive a run.
+struct FileStorage(PathBuf);
+
+impl FileStorage {
+    /// Bytes that have never been written read as `0xff`, like erased EEPROM.
+    const ERASED: u8 = 0xff;
+
+    fn bytes(&self) -> Vec<u8> {
+        fs::read(&self.0).unwrap_or_default()
+    }
+}
+
+impl Storage for FileStorage {
+    fn read(&mut self, offset: u16, buffer: &mut [u8]) {
+        let bytes = self.bytes();
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            *byte = *bytes
+                .get(usize::from(offset) + index)
+                .unwrap_or(&Self::ERASED);
+        }
+    }
+
+    fn write(&mut self, offset: u16, bytes: &[u8]) {
+        let mut stored = self.bytes();
+        let end = usize::from(offset) + bytes.len();
+        if stored.len() < end {
+            stored.resize(end, Self::ERASED);
+        }
+        stored[usize::from(offset)..end].copy_from_slice(bytes);
+        fs::write(&self.0, stored).unwrap();
+    }
+}
+
+fn stty(args: &[&str]) -> String {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .expect("stty should be available on the host");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let game = args.next();
+    let recording = args.next().map(|hex| Recording::from_hex(hex.as_bytes()));
+    if !matches!(game.as_deref(), None | Some("ttt" | "hexapawn"))
+        || matches!(recording, Some(None))
+        || args.next().is_some()
+    {
+        eprintln!("usage: ttt-sim [ttt|hexapawn] [recording]");
+        exit(2);
+    }
+
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    let mut runtime = Runtime::new(
+        Terminal::new(),
+        Keyboard::new(),
+        SimClock(Instant::now()),
+        FileStorage(env::temp_dir().join("ttt-sim.eeprom")),
+        seed as u16,
+    );
+
+    let mut recording = recording.flatten();
+    loop {
+        match (game.as_deref(), recording.take()) {
+            (Some("hexapawn"), Some(recording)) => runtime.replay(&recording, |first| {
+                Hexapawn::<WIDTH, HEIGHT>::new(first, COMPUTER)
+            }),
+            (Some("hexapawn"), None) => {
+                runtime.play(|first| Hexapawn::<WIDTH, HEIGHT>::new(first, COMPUTER))
+            }
+            (_, Some(recording)) => {
+                runtime.replay(&recording, |first| TicTacToe::<WIDTH>::new(first, COMPUTER))
+            }
+            (_, None) => runtime.play(|first| TicTacToe::<WIDTH>::new(first, COMPUTER)),
+        }
+        let hex: String = runtime.recording().hex().map(char::from).collect();
+        let full = if runtime.recording().full() {
+            " (full, so later input is missing)"
+        } else {
+            ""
+        };
+        print!("\x1b[7H\x1b[Krecording: {hex}{full}\r\n");
+    }
+}
@@ -0,0 +1,122 @@
+use core::array::from_fn;
+
+use crate::{
+    board::Board,
+    display::DisplayPixel,
+    position::Position,
+    time::{Duration, Instant},
+};
+
+/// The dimmest [`Effect::Pulse`] gets, so pulsing cells never disappear.
+const PULSE_FLOOR: u8 = DisplayPixel::MAX / 4;
+
+/// Only the cell at `position`, for effects that apply to some cells.
+pub fn cell<const W: usize, const H: usize>(position: Position) -> Board<bool, W, H> {
+    from_fn(|y| from_fn(|x| position.x == x && position.y == y))
+}
+
+/// How bright a pulse is `elapsed` into a `period`, fading from
+/// [`DisplayPixel::MAX`] to [`PULSE_FLOOR`] and back.
+pub(crate) fn pulse_level(elapsed: Duration, period: Duration) -> u8 {
+    let distance = Duration::from_millis((elapsed * 2).as_millis().abs_diff(period.as_millis()));
+    let range = u32::from(DisplayPixel::MAX - PULSE_FLOOR);
+    PULSE_FLOOR + u8::try_from(distance * range / period).unwrap()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Playback {
+    /// Plays once, after which the frame is left as it is.
+    Once,
+    Loop,
+}
+
+/// What an animation does to the frame it is drawn over, over one period.
+#[derive(Clone, Copy)]
+pub enum Effect<const W: usize, const H: usize> {
+    /// Turns `cells` off for the second half.
+    Blink { cells: Board<bool, W, H> },
+    /// Fades `cells` down and back up.
+    Pulse { cells: Board<bool, W, H> },
+    /// Alternates `on` and `off` between neighbouring cells, swapping them
+    /// halfway through.
+    Checkerboard { on: DisplayPixel, off: DisplayPixel },
+}
+
+impl<const W: usize, const H: usize> Effect<W, H> {
+    fn draw(
+        self,
+        base: Board<DisplayPixel, W, H>,
+        elapsed: Duration,
+        period: Duration,
+    ) -> Board<DisplayPixel, W, H> {
+        let first_half = elapsed < period / 2;
+        match self {
+            Self::Blink { cells } => {
+                from_fn(|y| from_fn(|x| base[y][x].and(first_half || !cells[y][x])))
+            }
+            Self::Pulse { cells } => {
+                let level = pulse_level(elapsed, period);
+                from_fn(|y| {
+                    from_fn(|x| {
+                        if cells[y][x] {
+                            base[y][x].scale(level)
+                        } else {
+                            base[y][x]
+                        }
+                    })
+                })
+            }
+            Self::Checkerboard { on, off } => from_fn(|y| {
+                from_fn(|x| {
+                    let lit = (y * W + x) % 2 == usize::from(first_half);
+                    base[y][x].mix(if lit { on } else { off })
+                })
+            }),
+        }
+    }
+}
+
+/// An [`Effect`] being played. Its clock starts the first time it is drawn, so
+/// games can start one without knowing the time.
+#[derive(Clone, Copy)]
+pub struct Animation<const W: usize, const H: usize> {
+    effect: Effect<W, H>,
+    /// How long one repetition takes.
+    period: Duration,
+    playback: Playback,
+    started: Option<Instant>,
+}
+
+impl<const W: usize, const H: usize> Animation<W, H> {
+    pub const fn new(effect: Effect<W, H>, period: Duration, playback: Playback) -> Self {
+        assert!(period.as_millis() > 0, "an animation has to last some time");
+        Self {
+            effect,
+            period,
+            playback,
+            started: None,
+        }
+    }
+
+    /// Draws the animation over `base` as it looks at `now`.
+    pub fn draw(
+        &mut self,
+        base: Board<DisplayPixel, W, H>,
+        now: Instant,
+    ) -> Board<DisplayPixel, W, H> {
+        let elapsed = now - *self.started.get_or_insert(now);
+        match self.playback {
+            Playback::Once if elapsed >= self.period => base,
+            Playback::Once => self.effect.draw(base, elapsed, self.period),
+            Playback::Loop => self.effect.draw(base, elapsed % self.period, self.period),
+        }
+    }
+
+    /// Whether a [`Playback::Once`] animation has played through.
+    pub fn finished(&self, now: Instant) -> bool {
+        self.playback == Playback::Once
+            && self
+                .started
+                .is_some_and(|started| now - started >= self.period)
+    }
+}
@@ -0,0 +1,60 @@
+//! Dims the display in the dark, using an LDR from 5V to A6 with a fixed
+//! resistor from A6 to ground.
+
+use arduino_hal::{adc::channel::ADC6, Adc};
+use ttt::{
+    board::Board,
+    display::DisplayPixel,
+    io::FrameSink,
+    time::{Duration, Instant},
+};
+
+use crate::avr::millis::now;
+
+/// Time between readings of the LDR.
+const INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct AutoDim<D> {
+    display: D,
+    adc: Adc,
+    /// How bright it is around the board, from 1 to [`DisplayPixel::MAX`].
+    ambient: u8,
+    /// When the LDR is next read.
+    next_read: Instant,
+}
+
+impl<D> AutoDim<D> {
+    pub const fn new(display: D, adc: Adc) -> Self {
+        Self {
+            display,
+            adc,
+            ambient: DisplayPixel::MAX,
+            next_read: Instant::ZERO,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, D: FrameSink<W, H>> FrameSink<W, H> for AutoDim<D> {
+    fn write(&mut self, buffer: Board<DisplayPixel, W, H>) {
+        self.display
+            .write(buffer.map(|row| row.map(|pixel| pixel.scale(self.ambient))));
+    }
+
+    fn show(&mut self) {
+        if now().reached(self.next_read) {
+            self.next_read = now() + INTERVAL;
+            let reading = u32::from(self.adc.read_blocking(&ADC6));
+            let level = 1 + reading * u32::from(DisplayPixel::MAX - 1) / 1023;
+            self.ambient = u8::try_from(level).unwrap_or(DisplayPixel::MAX);
+        }
+        self.display.show();
+    }
+
+    fn wait_for_swap(&mut self) {
+        self.display.wait_for_swap();
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        self.display.set_brightness(level);
+    }
+}
@@ -0,0 +1,79 @@
+use arduino_hal::{
+    hal::port::Dynamic,
+    port::mode::{Input, OpenDrain, PullUp},
+    prelude::_unwrap_infallible_UnwrapInfallible,
+};
+use avr_hal_generic::{hal_v0::digital::v2::OutputPin, port::Pin};
+use core::{array::from_fn, convert::identity};
+
+use ttt::{io::InputSource, scan::ButtonScan};
+
+use crate::avr::sleep::PowerDown;
+
+pub struct Buttons<const W: usize, const H: usize> {
+    rows: [Pin<OpenDrain, Dynamic>; H],
+    columns: [Pin<Input<PullUp>, Dynamic>; W],
+    power_down: PowerDown,
+}
+
+impl<const W: usize, const H: usize> Buttons<W, H> {
+    pub fn scan(&mut self) -> ButtonScan<W, H> {
+        ButtonScan::new(from_fn(identity).map(|y| {
+            for (index, other) in self.rows.iter_mut().enumerate() {
+                other.set_state((index != y).into()).unwrap_infallible();
+            }
+            self.columns.each_ref().map(<Pin<Input<_>, _>>::is_low)
+        }))
+    }
+
+    pub const fn new(
+        rows: [Pin<OpenDrain, Dynamic>; H],
+        columns: [Pin<Input<PullUp>, Dynamic>; W],
+        power_down: PowerDown,
+    ) -> Self {
+        Self {
+            rows,
+            columns,
+            power_down,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> InputSource<W, H> for Buttons<W, H> {
+    fn read(&mut self) -> ButtonScan<W, H> {
+        self.scan()
+    }
+
+    /// Drives every row low, so that a press on any key pulls its column low
+    /// and wakes the CPU.
+    fn sleep(&mut self) {
+        for row in &mut self.rows {
+            row.set_low();
+        }
+        self.power_down
+            .until_pin_change(|| self.columns.iter().any(<Pin<Input<_>, _>>::is_low));
+    }
+}
+
+#[macro_export]
+macro_rules! pin_triple {
+    ($pins:expr, $mode:ident, $($pin:ident),+) => {
+        [$($pins.$pin.$mode().downgrade()),+]
+    };
+}
+
+macro_rules! buttons {
+    ($pins:expr, $exint:expr, $cpu:expr) => {{
+        use crate::{
+            avr::{buttons::Buttons, sleep::PowerDown},
+            pin_triple,
+        };
+        Buttons::new(
+            pin_triple!($pins, into_opendrain, a0, a1, a2),
+            pin_triple!($pins, into_pull_up_input, d11, d12, a3),
+            PowerDown::new($exint, $cpu),
+        )
+    }};
+}
+
+pub(crate) use buttons;
@@ -0,0 +1,110 @@
+//! A self-test for telling solder faults from firmware bugs, entered by
+//! holding the top left key at power-up. Results go to the serial port.
+
+use core::{array::from_fn, convert::Infallible};
+
+use arduino_hal::prelude::_unwrap_infallible_UnwrapInfallible;
+use ufmt::{uWrite, uwrite, uwriteln};
+
+use ttt::{
+    board::Board,
+    display::DisplayPixel,
+    io::FrameSink,
+    position::{pos, Position},
+    time::Duration,
+};
+
+use crate::{
+    avr::{
+        buttons::Buttons,
+        millis::{micros, now},
+    },
+    consts::{HEIGHT, WIDTH},
+};
+
+/// How long every key has to be let go within after entering, before the
+/// ones still pressed are counted as stuck.
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long each LED is lit for while stepping through them.
+const STEP: Duration = Duration::from_millis(500);
+
+pub fn run(
+    display: &mut impl FrameSink<WIDTH, HEIGHT>,
+    buttons: &mut Buttons<WIDTH, HEIGHT>,
+    serial: &mut impl uWrite<Error = Infallible>,
+) -> ! {
+    uwriteln!(serial, "diagnostics: let go of every key").unwrap_infallible();
+    let deadline = now() + RELEASE_TIMEOUT;
+    let mut scan = buttons.scan();
+    while scan.positions().next().is_some() && !now().reached(deadline) {
+        scan = buttons.scan();
+    }
+    let stuck = board(|position| scan.pressed(position));
+    for position in positions().filter(|position| stuck[position.y][position.x]) {
+        uwriteln!(serial, "stuck key at {},{}", position.x, position.y).unwrap_infallible();
+    }
+    let start = micros();
+    buttons.scan();
+    uwriteln!(serial, "scan takes {}us", micros().wrapping_sub(start)).unwrap_infallible();
+
+    uwriteln!(
+        serial,
+        "diagnostics: hold any key while the lit LED stays dark"
+    )
+    .unwrap_infallible();
+    for (name, colour) in [("red", DisplayPixel::red()), ("blue", DisplayPixel::blue())] {
+        for position in positions() {
+            display.write(board(|other| colour.and(other == position)));
+            let deadline = now() + STEP;
+            let mut dark = false;
+            while !now().reached(deadline) {
+                let scan = buttons.scan();
+                dark |= scan.positions().any(|key| !stuck[key.y][key.x]);
+                display.show();
+            }
+            if dark {
+                uwriteln!(serial, "dead {} LED at {},{}", name, position.x, position.y)
+                    .unwrap_infallible();
+            }
+        }
+    }
+
+    uwriteln!(serial, "diagnostics: press keys to check them").unwrap_infallible();
+    let mut ghosting = [[false; WIDTH]; HEIGHT];
+    let mut last = None;
+    loop {
+        let scan = buttons.scan();
+        let ghosts = scan.ghosts();
+        for position in positions() {
+            if ghosts[position.y][position.x] && !ghosting[position.y][position.x] {
+                ghosting[position.y][position.x] = true;
+                uwriteln!(serial, "ghosting at {},{}", position.x, position.y).unwrap_infallible();
+            }
+        }
+        if last != Some(scan) {
+            uwrite!(serial, "pressed:").unwrap_infallible();
+            for position in scan.positions() {
+                uwrite!(serial, " {},{}", position.x, position.y).unwrap_infallible();
+            }
+            uwriteln!(serial, "").unwrap_infallible();
+            last = Some(scan);
+        }
+        display.write(board(|position @ Position { x, y }| {
+            let flagged = stuck[y][x] || ghosting[y][x];
+            DisplayPixel::red()
+                .and(scan.pressed(position))
+                .mix(DisplayPixel::blue().and(flagged))
+        }));
+        display.show();
+    }
+}
+
+/// Every position, in reading order.
+fn positions() -> impl Iterator<Item = Position> {
+    (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| pos(x, y)))
+}
+
+/// A board with `cell` called for each position.
+fn board<C>(mut cell: impl FnMut(Position) -> C) -> Board<C, WIDTH, HEIGHT> {
+    from_fn(|y| from_fn(|x| cell(pos(x, y))))
+}
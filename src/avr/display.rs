@@ -0,0 +1,192 @@
+//! The LED matrix, scanned from the Timer2 interrupt.
+//!
+//! The interrupt handler has to find the scanner in a static, and a static
+//! can't be generic, so the driver only comes in the board's own
+//! [`WIDTH`]x[`HEIGHT`]. The `display!` macro wires up pins for that size
+//! too. A board of another size changes those constants rather than
+//! instantiating the driver again.
+
+use core::{array::from_fn, cell::RefCell, convert::Infallible};
+
+use arduino_hal::{
+    hal::port::Dynamic,
+    pac::TC2,
+    port::mode::{OpenDrain, Output},
+    prelude::_unwrap_infallible_UnwrapInfallible,
+};
+use avr_device::{
+    interrupt,
+    interrupt::{free, Mutex},
+};
+use avr_hal_generic::{hal_v0::digital::v2::OutputPin, port::Pin};
+
+use ttt::{
+    board::Board,
+    display::{DisplayPixel, DEPTH},
+    io::FrameSink,
+};
+
+use crate::consts::{HEIGHT, WIDTH};
+
+const PRESCALER: u32 = 256;
+/// Timer counts the least significant bit of a pixel is lit for. Each row is
+/// lit for `UNIT_COUNTS * DisplayPixel::MAX` counts in total, about 2 ms.
+const UNIT_COUNTS: u32 = 8;
+static SCANNER: Mutex<RefCell<Option<Scanner<WIDTH, HEIGHT>>>> = Mutex::new(RefCell::new(None));
+
+/// Lights each row in turn with bit-angle modulation: bit `n` of every
+/// pixel's levels is shown for `UNIT_COUNTS << n` counts, so a pixel's
+/// brightness is proportional to its level.
+struct Scanner<const W: usize, const H: usize> {
+    /// The frame being scanned.
+    front: Board<DisplayPixel, W, H>,
+    /// The latest frame written, swapped in at the start of the next scan so
+    /// a frame is never shown half old and half new.
+    back: Option<Board<DisplayPixel, W, H>>,
+    row: usize,
+    bit: u32,
+    timer: TC2,
+    blue_columns: [Pin<Output, Dynamic>; W],
+    red_columns: [Pin<Output, Dynamic>; W],
+    led_rows: [Pin<OpenDrain, Dynamic>; H],
+}
+
+impl<const W: usize, const H: usize> Scanner<W, H> {
+    fn next_plane(&mut self) {
+        self.bit = (self.bit + 1) % DEPTH;
+        for column in [self.red_columns.each_mut(), self.blue_columns.each_mut()].as_flattened_mut()
+        {
+            column.set_low();
+        }
+        if self.bit == 0 {
+            self.row = (self.row + 1) % H;
+            if self.row == 0 {
+                if let Some(back) = self.back.take() {
+                    self.front = back;
+                }
+            }
+            for (row, active) in self
+                .led_rows
+                .iter_mut()
+                .zip(from_fn::<_, H, _>(|index| index == self.row))
+            {
+                row.set_state((!active).into()).unwrap_infallible();
+            }
+        }
+        let row = &self.front[self.row];
+        for (x, pixel) in row.iter().enumerate() {
+            write_to(
+                *pixel,
+                self.bit,
+                &mut self.red_columns[x],
+                &mut self.blue_columns[x],
+            )
+            .unwrap_infallible();
+        }
+        self.timer
+            .ocr2a
+            .write(|w| w.bits(u8::try_from((UNIT_COUNTS << self.bit) - 1).unwrap()));
+    }
+}
+
+/// The LED matrix, scanned from the Timer2 interrupt.
+pub struct Display {
+    brightness: u8,
+}
+
+impl Display {
+    pub fn new(
+        tc2: TC2,
+        blue_columns: [Pin<Output, Dynamic>; WIDTH],
+        red_columns: [Pin<Output, Dynamic>; WIDTH],
+        led_rows: [Pin<OpenDrain, Dynamic>; HEIGHT],
+    ) -> Self {
+        tc2.tccr2a.write(|w| w.wgm2().ctc());
+        tc2.ocr2a
+            .write(|w| w.bits(u8::try_from(UNIT_COUNTS - 1).unwrap()));
+        tc2.tccr2b.write(|w| match PRESCALER {
+            32 => w.cs2().prescale_32(),
+            64 => w.cs2().prescale_64(),
+            128 => w.cs2().prescale_128(),
+            256 => w.cs2().prescale_256(),
+            _ => panic!(),
+        });
+        tc2.timsk2.write(|w| w.ocie2a().set_bit());
+
+        free(|cs| {
+            SCANNER.borrow(cs).replace(Some(Scanner {
+                front: [[DisplayPixel::default(); WIDTH]; HEIGHT],
+                back: None,
+                row: 0,
+                bit: 0,
+                timer: tc2,
+                blue_columns,
+                red_columns,
+                led_rows,
+            }));
+        });
+
+        Self {
+            brightness: DisplayPixel::MAX,
+        }
+    }
+}
+
+impl FrameSink<WIDTH, HEIGHT> for Display {
+    fn write(&mut self, buffer: Board<DisplayPixel, WIDTH, HEIGHT>) {
+        let buffer = buffer.map(|row| row.map(|pixel| pixel.scale(self.brightness)));
+        free(|cs| {
+            if let Some(scanner) = SCANNER.borrow(cs).borrow_mut().as_mut() {
+                scanner.back = Some(buffer);
+            }
+        });
+    }
+
+    fn wait_for_swap(&mut self) {
+        while free(|cs| {
+            SCANNER
+                .borrow(cs)
+                .borrow()
+                .as_ref()
+                .is_some_and(|scanner| scanner.back.is_some())
+        }) {}
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        self.brightness = level;
+    }
+}
+
+#[interrupt(atmega328p)]
+fn TIMER2_COMPA() {
+    free(|cs| {
+        if let Some(scanner) = SCANNER.borrow(cs).borrow_mut().as_mut() {
+            scanner.next_plane();
+        }
+    });
+}
+
+fn write_to(
+    pixel: DisplayPixel,
+    bit: u32,
+    red_pin: &mut Pin<Output, Dynamic>,
+    blue_pin: &mut Pin<Output, Dynamic>,
+) -> Result<(), Infallible> {
+    red_pin.set_state((pixel.red_level() >> bit & 1 == 1).into())?;
+    blue_pin.set_state((pixel.blue_level() >> bit & 1 == 1).into())?;
+    Ok(())
+}
+
+macro_rules! display {
+    ($pins:expr, $tc2:expr) => {{
+        use crate::{avr::display::Display, pin_triple};
+        Display::new(
+            $tc2,
+            pin_triple!($pins, into_output, d2, d4, d6),
+            pin_triple!($pins, into_output, d3, d5, d7),
+            pin_triple!($pins, into_opendrain, d8, d9, d10),
+        )
+    }};
+}
+
+pub(crate) use display;
@@ -0,0 +1,52 @@
+//! Moves typed over the USB serial port, for boards whose key matrix is
+//! damaged and for playing from a laptop.
+
+use arduino_hal::prelude::_embedded_hal_serial_Read;
+
+use ttt::{
+    io::{Event, InputSource},
+    keypad::Keypad,
+    scan::ButtonScan,
+};
+
+/// The keys of `keys`, plus whatever is typed into `serial`.
+pub struct SerialKeypad<I, R, const W: usize, const H: usize> {
+    keys: I,
+    serial: R,
+    keypad: Keypad<W, H>,
+}
+
+impl<I, R, const W: usize, const H: usize> SerialKeypad<I, R, W, H> {
+    pub const fn new(keys: I, serial: R) -> Self {
+        Self {
+            keys,
+            serial,
+            keypad: Keypad::new(),
+        }
+    }
+}
+
+impl<I, R, const W: usize, const H: usize> InputSource<W, H> for SerialKeypad<I, R, W, H>
+where
+    I: InputSource<W, H>,
+    R: _embedded_hal_serial_Read<u8>,
+{
+    fn read(&mut self) -> ButtonScan<W, H> {
+        self.keys.read()
+    }
+
+    /// A byte typed wakes the board as a key does, through a pin change on
+    /// RXD. The USART is stopped while the board sleeps and only picks up
+    /// partway through that byte, so it is lost or misread. A space is the
+    /// safest thing to wake the board with.
+    fn sleep(&mut self) {
+        self.keys.sleep();
+    }
+
+    fn event(&mut self) -> Option<Event> {
+        while let Ok(byte) = self.serial.read() {
+            self.keypad.feed(byte);
+        }
+        self.keypad.pop()
+    }
+}
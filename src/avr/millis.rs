@@ -0,0 +1,100 @@
+use core::cell::Cell;
+
+use arduino_hal::pac::{CPU, TC0};
+use avr_device::{
+    asm::sleep,
+    interrupt,
+    interrupt::{enable, free, Mutex},
+};
+use ttt::{io::Clock, time::Instant};
+
+const PRESCALER: u32 = 64;
+/// Timer counts per compare match, which at 16 MHz is exactly a millisecond.
+const TIMER_COUNTS: u8 = 250;
+const MICROS_PER_COUNT: u32 = PRESCALER / 16;
+const _: () = assert!(
+    PRESCALER * TIMER_COUNTS as u32 == 16_000,
+    "a compare match has to come every millisecond"
+);
+static MILLIS_COUNTER: Mutex<Cell<u32>> = Mutex::new(Cell::new(0));
+
+pub fn init(tc0: &TC0) {
+    tc0.tccr0a.write(|w| w.wgm0().ctc());
+    // The counter runs from 0 up to and including OCR0A.
+    tc0.ocr0a.write(|w| w.bits(TIMER_COUNTS - 1));
+    tc0.tccr0b.write(|w| match PRESCALER {
+        8 => w.cs0().prescale_8(),
+        64 => w.cs0().prescale_64(),
+        256 => w.cs0().prescale_256(),
+        1024 => w.cs0().prescale_1024(),
+        _ => panic!(),
+    });
+    tc0.timsk0.write(|w| w.ocie0a().set_bit());
+
+    free(|cs| {
+        MILLIS_COUNTER.borrow(cs).set(0);
+    });
+
+    unsafe { enable() };
+}
+
+#[interrupt(atmega328p)]
+fn TIMER0_COMPA() {
+    free(|cs| {
+        let counter_cell = MILLIS_COUNTER.borrow(cs);
+        let counter = counter_cell.get();
+        counter_cell.set(counter.wrapping_add(1));
+    });
+}
+
+/// Milliseconds since [`init`].
+pub fn millis() -> u32 {
+    free(|cs| MILLIS_COUNTER.borrow(cs).get())
+}
+
+/// Microseconds since [`init`], in steps of four, wrapping about every 71
+/// minutes.
+pub fn micros() -> u32 {
+    // SAFETY: only reads registers, which nothing else does anything to
+    // after `init`.
+    let tc0 = unsafe { &*TC0::ptr() };
+    free(|cs| {
+        let mut millis = MILLIS_COUNTER.borrow(cs).get();
+        let mut counts = tc0.tcnt0.read().bits();
+        // A compare match while interrupts are off hasn't been counted yet.
+        // The counter may have wrapped before or after it was read, so it is
+        // read again once it certainly has.
+        if tc0.tifr0.read().ocf0a().bit_is_set() {
+            millis = millis.wrapping_add(1);
+            counts = tc0.tcnt0.read().bits();
+        }
+        millis
+            .wrapping_mul(1000)
+            .wrapping_add(u32::from(counts) * MICROS_PER_COUNT)
+    })
+}
+
+/// [`millis`] as an [`Instant`], for comparing safely across the wrap.
+pub fn now() -> Instant {
+    Instant::from_millis(millis())
+}
+
+/// The [`millis`] counter as a [`Clock`] for games.
+pub struct Timer0;
+
+impl Clock for Timer0 {
+    fn now(&self) -> Instant {
+        now()
+    }
+
+    /// Idles the CPU until the next interrupt, which is at most a
+    /// millisecond away. The timers and the display keep running.
+    fn wait(&self) {
+        // SAFETY: SMCR is otherwise only used by `PowerDown`, which runs from
+        // the main loop too, and so never at the same time.
+        let cpu = unsafe { &*CPU::ptr() };
+        cpu.smcr.write(|w| w.sm().idle().se().set_bit());
+        sleep();
+        cpu.smcr.write(|w| w.se().clear_bit());
+    }
+}
@@ -0,0 +1,152 @@
+//! In place of `panic-halt`: a panic shows a red X blinking on the matrix,
+//! logs where it happened over serial, and keeps the location in EEPROM so
+//! the next boot can report it too.
+
+use core::{convert::Infallible, panic::PanicInfo};
+
+use arduino_hal::{
+    default_serial, delay_ms, delay_us,
+    hal::{port::Dynamic, wdt::Wdt},
+    pins,
+    port::{mode::Output, Pin},
+    prelude::_unwrap_infallible_UnwrapInfallible,
+    Eeprom, Peripherals,
+};
+use avr_device::interrupt;
+use ufmt::{uWrite, uwriteln};
+
+use ttt::io::Storage;
+
+use crate::{
+    consts::{HEIGHT, WIDTH},
+    pin_triple,
+};
+
+/// Where the location starts in EEPROM, clear of the settings.
+const OFFSET: u16 = 16;
+/// Marks a location written by [`Location::store`].
+const MAGIC: u8 = 0xc5;
+/// Bytes kept from the end of the file's path.
+const FILE_LEN: usize = 12;
+/// How long each half of a blink of the X lasts.
+const BLINK_MS: u32 = 400;
+/// How long each row of the X is lit for while the rows are scanned by hand.
+const ROW_US: u32 = 2000;
+/// Scans of every row in the lit half of a blink.
+const SCANS: u32 = BLINK_MS * 1000 / (ROW_US * HEIGHT as u32);
+
+/// Where a panic happened.
+pub struct Location {
+    /// The end of the path, padded with zeros.
+    file: [u8; FILE_LEN],
+    line: u16,
+    column: u16,
+}
+
+impl Location {
+    fn new(location: &core::panic::Location) -> Self {
+        let path = location.file().as_bytes();
+        let tail = &path[path.len().saturating_sub(FILE_LEN)..];
+        let mut file = [0; FILE_LEN];
+        file[..tail.len()].copy_from_slice(tail);
+        Self {
+            file,
+            line: u16::try_from(location.line()).unwrap_or(u16::MAX),
+            column: u16::try_from(location.column()).unwrap_or(u16::MAX),
+        }
+    }
+
+    /// The location stored by the last panic, if there was one since it was
+    /// last taken.
+    pub fn take(storage: &mut impl Storage) -> Option<Self> {
+        let mut bytes = [0; 5 + FILE_LEN];
+        storage.read(OFFSET, &mut bytes);
+        if bytes[0] != MAGIC {
+            return None;
+        }
+        storage.write(OFFSET, &[0]);
+        let mut file = [0; FILE_LEN];
+        file.copy_from_slice(&bytes[5..]);
+        Some(Self {
+            file,
+            line: u16::from_le_bytes([bytes[1], bytes[2]]),
+            column: u16::from_le_bytes([bytes[3], bytes[4]]),
+        })
+    }
+
+    fn bytes(&self) -> [u8; 5 + FILE_LEN] {
+        let mut bytes = [0; 5 + FILE_LEN];
+        let [line_low, line_high] = self.line.to_le_bytes();
+        let [column_low, column_high] = self.column.to_le_bytes();
+        bytes[..5].copy_from_slice(&[MAGIC, line_low, line_high, column_low, column_high]);
+        bytes[5..].copy_from_slice(&self.file);
+        bytes
+    }
+
+    pub fn log(&self, serial: &mut impl uWrite<Error = Infallible>) {
+        let len = self
+            .file
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(FILE_LEN);
+        let file = core::str::from_utf8(&self.file[..len]).unwrap_or("?");
+        uwriteln!(
+            serial,
+            "panicked at ...{}:{}:{}",
+            file,
+            self.line,
+            self.column
+        )
+        .unwrap_infallible();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    interrupt::disable();
+    // SAFETY: nothing else runs from here on, so whatever held the
+    // peripherals never touches them again.
+    let peripherals = unsafe { Peripherals::steal() };
+    // The watchdog would reset the board before anyone saw the X.
+    Wdt::new(peripherals.WDT, &peripherals.CPU.mcusr).stop();
+    let pins = pins!(peripherals);
+
+    let mut serial = default_serial!(peripherals, pins, 57600);
+    match info.location().map(Location::new) {
+        Some(location) => {
+            location.log(&mut serial);
+            // Nothing more can be done if this fails, and panicking again
+            // would only end up back here.
+            let _ = Eeprom::new(peripherals.EEPROM).write(OFFSET, &location.bytes());
+        }
+        None => uwriteln!(serial, "panicked").unwrap_infallible(),
+    }
+
+    for mut blue in pin_triple!(pins, into_output, d2, d4, d6) {
+        blue.set_low();
+    }
+    let mut red_columns = pin_triple!(pins, into_output, d3, d5, d7);
+    let mut led_rows = pin_triple!(pins, into_opendrain_high, d8, d9, d10);
+    loop {
+        for _ in 0..SCANS {
+            for (y, row) in led_rows.iter_mut().enumerate() {
+                light_x_row(y, &mut red_columns);
+                row.set_low();
+                delay_us(ROW_US);
+                row.set_high();
+            }
+        }
+        delay_ms(BLINK_MS);
+    }
+}
+
+/// Lights the red LEDs of row `y` that are part of an X.
+fn light_x_row(y: usize, red_columns: &mut [Pin<Output, Dynamic>; WIDTH]) {
+    for (x, column) in red_columns.iter_mut().enumerate() {
+        if x == y || x == WIDTH - 1 - y {
+            column.set_high();
+        } else {
+            column.set_low();
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use core::arch::asm;
+
+use arduino_hal::pac::{ADC, CPU, EXINT};
+use avr_device::interrupt;
+
+/// PCINT3 and PCINT4, on the d11 and d12 columns.
+const PORT_B_COLUMNS: u8 = 1 << 3 | 1 << 4;
+/// PCINT11, on the a3 column.
+const PORT_C_COLUMNS: u8 = 1 << 3;
+/// PCINT16, on RXD, which the start bit of a byte typed over serial pulls
+/// low.
+const PORT_D_SERIAL: u8 = 1 << 0;
+/// PCIE0, PCIE1 and PCIE2, for ports B, C and D.
+const PORTS: u8 = 0b111;
+
+/// Power-down sleep, woken by a pin change on any of the button columns or
+/// on the serial port's receive line.
+pub struct PowerDown {
+    exint: EXINT,
+    cpu: CPU,
+}
+
+impl PowerDown {
+    pub fn new(exint: EXINT, cpu: CPU) -> Self {
+        exint.pcmsk0.write(|w| w.bits(PORT_B_COLUMNS));
+        exint.pcmsk1.write(|w| w.bits(PORT_C_COLUMNS));
+        exint.pcmsk2.write(|w| w.bits(PORT_D_SERIAL));
+        Self { exint, cpu }
+    }
+
+    /// Stops every clock until a column changes, which only happens on a
+    /// press while the rows are driven low, or a byte starts arriving over
+    /// serial. Timers, and so `millis`, pause meanwhile. The ADC is turned
+    /// off too, as it keeps drawing current.
+    ///
+    /// A key already down wouldn't change its column, so `pressed` is asked
+    /// once any press from then on would wake the CPU, and the board stays
+    /// awake if it says so.
+    pub fn until_pin_change(&mut self, pressed: impl FnOnce() -> bool) {
+        // SAFETY: only ADEN changes, and it is put back before anything else
+        // can use the ADC.
+        let adc = unsafe { &*ADC::ptr() };
+        let adcsra = adc.adcsra.read().bits();
+        adc.adcsra
+            .write(|w| unsafe { w.bits(adcsra) }.aden().clear_bit());
+
+        interrupt::disable();
+        self.exint.pcifr.write(|w| unsafe { w.bits(PORTS) });
+        self.exint.pcicr.write(|w| unsafe { w.bits(PORTS) });
+        if pressed() {
+            unsafe { interrupt::enable() };
+        } else {
+            self.cpu.smcr.write(|w| w.sm().pdown().se().set_bit());
+            // SEI takes effect only after the next instruction, so a press
+            // from here on wakes the CPU out of SLEEP rather than being
+            // handled before it and leaving the board asleep until the key is
+            // let go.
+            unsafe { asm!("sei", "sleep") };
+            self.cpu.smcr.write(|w| w.se().clear_bit());
+        }
+        self.exint.pcicr.write(|w| unsafe { w.bits(0) });
+
+        adc.adcsra.write(|w| unsafe { w.bits(adcsra) });
+    }
+}
+
+/// Only there to wake the CPU.
+#[interrupt(atmega328p)]
+fn PCINT0() {}
+
+#[interrupt(atmega328p)]
+fn PCINT1() {}
+
+#[interrupt(atmega328p)]
+fn PCINT2() {}
@@ -0,0 +1,20 @@
+use arduino_hal::Eeprom;
+use ttt::io::Storage;
+
+pub struct EepromStorage(Eeprom);
+
+impl EepromStorage {
+    pub const fn new(eeprom: Eeprom) -> Self {
+        Self(eeprom)
+    }
+}
+
+impl Storage for EepromStorage {
+    fn read(&mut self, offset: u16, buffer: &mut [u8]) {
+        self.0.read(offset, buffer).unwrap();
+    }
+
+    fn write(&mut self, offset: u16, bytes: &[u8]) {
+        self.0.write(offset, bytes).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+//! Resets the board if the runtime stops reading the keys, which it does on
+//! every pass unless something has hung.
+
+use arduino_hal::hal::wdt::{Timeout, Wdt};
+use ttt::{
+    io::{Event, InputSource},
+    scan::ButtonScan,
+};
+
+/// Far longer than any pass of the runtime takes.
+const TIMEOUT: Timeout = Timeout::Ms2000;
+
+/// Feeds the watchdog every time `input` is read.
+pub struct Watched<I> {
+    input: I,
+    watchdog: Wdt,
+}
+
+impl<I> Watched<I> {
+    /// Starts `watchdog`.
+    pub fn new(input: I, mut watchdog: Wdt) -> Self {
+        watchdog.start(TIMEOUT).unwrap();
+        Self { input, watchdog }
+    }
+}
+
+impl<I: InputSource<W, H>, const W: usize, const H: usize> InputSource<W, H> for Watched<I> {
+    fn read(&mut self) -> ButtonScan<W, H> {
+        self.watchdog.feed();
+        self.input.read()
+    }
+
+    /// Stops the watchdog meanwhile, as it keeps running in power-down and
+    /// would reset the board.
+    fn sleep(&mut self) {
+        self.watchdog.stop();
+        self.input.sleep();
+        self.watchdog.start(TIMEOUT).unwrap();
+    }
+
+    fn event(&mut self) -> Option<Event> {
+        self.input.event()
+    }
+}
@@ -0,0 +1,152 @@
+use core::array::from_fn;
+
+use crate::{
+    board::Board,
+    io::Event,
+    position::pos,
+    queue::Queue,
+    scan::ButtonScan,
+    time::{Duration, Instant},
+};
+
+/// How long a key has to read the same before the change counts.
+const DEBOUNCE: Duration = Duration::from_millis(20);
+/// How long a key is held before it starts repeating.
+const REPEAT_DELAY: Duration = Duration::from_millis(500);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(150);
+/// Events from one reading, which [`crate::gesture::Gestures`] takes out
+/// before the next. A reading adds at most one per key, so this covers all
+/// but one key of a 3x3 matrix changing at once.
+const CAPACITY: usize = 8;
+
+#[derive(Clone, Copy, Default)]
+struct Key {
+    /// The level last read, bounce and all.
+    raw: bool,
+    /// When `raw` last changed.
+    changed_at: Instant,
+    pressed: bool,
+    /// Repeats sent since the key was pressed.
+    repeats: u32,
+}
+
+/// Turns raw key levels into a queue of press, release and repeat events,
+/// timing each key on its own.
+pub struct Debouncer<const W: usize, const H: usize> {
+    keys: Board<Key, W, H>,
+    queue: Queue<Event, CAPACITY>,
+}
+
+impl<const W: usize, const H: usize> Debouncer<W, H> {
+    pub fn new() -> Self {
+        Self {
+            keys: [[Key::default(); W]; H],
+            queue: Queue::new(),
+        }
+    }
+
+    /// Queues whatever changed since the last reading, `scan` being taken at
+    /// `now`. Readings that might hold phantom presses are skipped.
+    pub fn update(&mut self, scan: ButtonScan<W, H>, now: Instant) {
+        if scan.trustworthy() {
+            self.update_trusted(scan, now);
+        }
+    }
+
+    /// Like [`Self::update`], but takes `scan` as it reads, phantoms and all.
+    pub fn update_trusted(&mut self, scan: ButtonScan<W, H>, now: Instant) {
+        for (y, row) in self.keys.iter_mut().enumerate() {
+            for (x, key) in row.iter_mut().enumerate() {
+                let level = scan.pressed(pos(x, y));
+                if level != key.raw {
+                    key.raw = level;
+                    key.changed_at = now;
+                    continue;
+                }
+                let held = now - key.changed_at;
+                let event = if key.raw != key.pressed && held >= DEBOUNCE {
+                    key.pressed = key.raw;
+                    key.repeats = 0;
+                    if key.pressed {
+                        Event::Press
+                    } else {
+                        Event::Release
+                    }
+                } else if key.pressed && held >= REPEAT_DELAY + REPEAT_INTERVAL * key.repeats {
+                    key.repeats += 1;
+                    Event::Repeat
+                } else {
+                    continue;
+                };
+                self.queue.push(event(pos(x, y)));
+            }
+        }
+    }
+
+    /// The next press, release or repeat, keys that changed in the same
+    /// reading coming in reading order.
+    pub fn pop(&mut self) -> Option<Event> {
+        self.queue.pop()
+    }
+
+    /// Every key held down, once debounced.
+    pub fn held(&self) -> Board<bool, W, H> {
+        from_fn(|y| from_fn(|x| self.keys[y][x].pressed))
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Debouncer<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Debouncer;
+    use crate::{
+        io::Event,
+        position::{pos, Position},
+        testing::{hold, scan},
+        time::Instant,
+    };
+
+    #[test]
+    fn ignores_bounce() {
+        let mut keys = Debouncer::new();
+        for millis in 0..100 {
+            let level: &[Position] = if millis % 10 < 5 { &[pos(1, 1)] } else { &[] };
+            keys.update(scan(level), Instant::from_millis(millis));
+        }
+        assert_eq!(keys.pop(), None);
+    }
+
+    #[test]
+    fn presses_repeats_and_releases() {
+        let mut keys = Debouncer::new();
+        assert_eq!(
+            hold(&mut keys, &[pos(1, 1)], 0, 21),
+            [Event::Press(pos(1, 1))]
+        );
+        assert_eq!(hold(&mut keys, &[pos(1, 1)], 21, 500), []);
+        assert_eq!(
+            hold(&mut keys, &[pos(1, 1)], 500, 800),
+            [Event::Repeat(pos(1, 1)), Event::Repeat(pos(1, 1))]
+        );
+        assert!(keys.held()[1][1]);
+        assert_eq!(hold(&mut keys, &[], 800, 830), [Event::Release(pos(1, 1))]);
+        assert!(!keys.held()[1][1]);
+    }
+
+    #[test]
+    fn skips_readings_with_phantoms() {
+        let mut keys = Debouncer::new();
+        let rectangle = [pos(0, 0), pos(2, 0), pos(0, 1), pos(2, 1)];
+        assert_eq!(hold(&mut keys, &rectangle, 0, 50), []);
+
+        for millis in 0..50 {
+            keys.update_trusted(scan(&rectangle), Instant::from_millis(millis));
+        }
+        assert_eq!(core::iter::from_fn(|| keys.pop()).count(), 4);
+    }
+}
@@ -1,142 +1,127 @@
-use crate::millis::millis;
-use core::{array::from_fn, convert::Infallible};
+use crate::player::Player;
 
-use arduino_hal::{
-    hal::port::Dynamic,
-    port::mode::{OpenDrain, Output},
-    prelude::_unwrap_infallible_UnwrapInfallible,
-};
-use avr_hal_generic::{hal_v0::digital::v2::OutputPin, port::Pin};
+/// Bits of intensity per colour channel.
+pub const DEPTH: u32 = 4;
 
-use crate::{board::Board, consts::SIZE, player::Player};
-
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
 pub struct DisplayPixel {
-    red: bool,
-    blue: bool,
+    red: u8,
+    blue: u8,
 }
 
 impl DisplayPixel {
-    pub const fn filter(&mut self, bool: bool) -> &mut Self {
-        self.red &= bool;
-        self.blue &= bool;
-        self
+    /// Full intensity of a channel.
+    pub const MAX: u8 = (1 << DEPTH) - 1;
+
+    /// Levels above [`Self::MAX`] are clamped to it.
+    pub const fn new(red: u8, blue: u8) -> Self {
+        Self {
+            red: clamp(red),
+            blue: clamp(blue),
+        }
     }
 
     pub const fn and(self, bool: bool) -> Self {
-        Self {
-            red: self.red && bool,
-            blue: self.blue && bool,
+        if bool {
+            self
+        } else {
+            Self::new(0, 0)
         }
     }
 
     pub const fn red() -> Self {
-        Self {
-            red: true,
-            blue: false,
-        }
+        Self::new(Self::MAX, 0)
     }
 
     pub const fn blue() -> Self {
-        Self {
-            red: false,
-            blue: true,
-        }
+        Self::new(0, Self::MAX)
     }
 
-    pub const fn red_if(cond: bool) -> Self {
+    /// Dims both channels to `level` out of [`Self::MAX`] of what they were.
+    /// A channel that was lit stays lit unless `level` is 0, so dim pixels
+    /// such as ghosts don't vanish at a low brightness.
+    pub const fn scale(self, level: u8) -> Self {
         Self {
-            red: cond,
-            blue: !cond,
+            red: scale(self.red, level),
+            blue: scale(self.blue, level),
         }
     }
 
-    pub fn or_player(&mut self, player: Player) -> &mut Self {
-        (*match player {
+    /// Dims just `player`'s channel to `level` out of [`Self::MAX`].
+    pub fn scale_player(mut self, player: Player, level: u8) -> Self {
+        let channel = match player {
             Player::Red => &mut self.red,
             Player::Blue => &mut self.blue,
-        }) = true;
+        };
+        *channel = scale(*channel, level);
         self
     }
 
-    pub fn write_to(
-        self,
-        red_pin: &mut Pin<Output, Dynamic>,
-        blue_pin: &mut Pin<Output, Dynamic>,
-    ) -> Result<(), Infallible> {
-        red_pin.set_state(self.red.into())?;
-        blue_pin.set_state(self.blue.into())?;
-        Ok(())
+    /// The brighter of each channel, so that a dim red and a dim blue make a
+    /// dim purple.
+    pub const fn mix(self, other: Self) -> Self {
+        Self {
+            red: brighter(self.red, other.red),
+            blue: brighter(self.blue, other.blue),
+        }
     }
-}
 
-impl From<Player> for DisplayPixel {
-    fn from(player: Player) -> Self {
-        match player {
-            Player::Red => Self::red(),
-            Player::Blue => Self::blue(),
-        }
+    pub const fn red_level(self) -> u8 {
+        self.red
+    }
+
+    pub const fn blue_level(self) -> u8 {
+        self.blue
     }
 }
 
-pub struct Display {
-    buffer: Board<DisplayPixel>,
-    blue_columns: [Pin<Output, Dynamic>; SIZE],
-    red_columns: [Pin<Output, Dynamic>; SIZE],
-    led_rows: [Pin<OpenDrain, Dynamic>; SIZE],
+const fn clamp(level: u8) -> u8 {
+    if level > DisplayPixel::MAX {
+        DisplayPixel::MAX
+    } else {
+        level
+    }
 }
 
-impl Display {
-    pub const fn new(
-        buffer: Board<DisplayPixel>,
-        blue_columns: [Pin<Output, Dynamic>; SIZE],
-        red_columns: [Pin<Output, Dynamic>; SIZE],
-        led_rows: [Pin<OpenDrain, Dynamic>; SIZE],
-    ) -> Self {
-        Self {
-            buffer,
-            blue_columns,
-            red_columns,
-            led_rows,
-        }
+const fn scale(channel: u8, level: u8) -> u8 {
+    let scaled = (channel as u16 * clamp(level) as u16 / DisplayPixel::MAX as u16) as u8;
+    if scaled == 0 && channel > 0 && level > 0 {
+        1
+    } else {
+        scaled
     }
+}
 
-    pub fn write(&mut self, buffer: Board<DisplayPixel>) {
-        self.buffer = buffer;
+const fn brighter(a: u8, b: u8) -> u8 {
+    if a > b {
+        a
+    } else {
+        b
     }
+}
 
-    pub fn show(&mut self) {
-        let y = millis() as usize % SIZE;
-        for column in [self.red_columns.each_mut(), self.blue_columns.each_mut()].as_flattened_mut()
-        {
-            column.set_low();
-        }
-        for (row, active) in self
-            .led_rows
-            .iter_mut()
-            .zip(from_fn::<_, SIZE, _>(|index| index == y))
-        {
-            row.set_state((!active).into()).unwrap_infallible();
-        }
-        let row = &self.buffer[y];
-        for (x, pixel) in row.iter().enumerate() {
-            pixel
-                .write_to(&mut self.red_columns[x], &mut self.blue_columns[x])
-                .unwrap_infallible();
+impl From<Player> for DisplayPixel {
+    fn from(player: Player) -> Self {
+        match player {
+            Player::Red => Self::red(),
+            Player::Blue => Self::blue(),
         }
     }
 }
 
-macro_rules! display {
-    ($pins:expr, $initial: expr) => {{
-        use crate::{display::Display, pin_triple};
-        Display::new(
-            $initial,
-            pin_triple!($pins, into_output, d2, d4, d6),
-            pin_triple!($pins, into_output, d3, d5, d7),
-            pin_triple!($pins, into_opendrain, d8, d9, d10),
-        )
-    }};
+#[cfg(test)]
+mod tests {
+    use super::DisplayPixel;
+    use crate::{animation::pulse_level, time::Duration};
+
+    #[test]
+    fn dim_pixels_stay_lit_at_the_lowest_brightness() {
+        let ghost = DisplayPixel::blue().scale(DisplayPixel::MAX / 4);
+        assert_eq!(ghost.scale(1).blue_level(), 1);
+        let period = Duration::from_millis(1000);
+        let faded = DisplayPixel::red().scale(pulse_level(Duration::from_millis(500), period));
+        assert_eq!(faded.scale(1).red_level(), 1);
+        assert_eq!(ghost.scale(1).red_level(), 0);
+        assert_eq!(ghost.scale(0).blue_level(), 0);
+    }
 }
-
-pub(crate) use display;
@@ -0,0 +1,316 @@
+//! Long presses, double taps and chords, recognised on top of the debounced
+//! key events.
+
+use core::array::from_fn;
+
+use crate::{
+    board::Board,
+    debounce::Debouncer,
+    io::Event,
+    position::Position,
+    queue::Queue,
+    scan::ButtonScan,
+    time::{Duration, Instant},
+};
+
+/// Gestures recognised but not yet passed on. The runtime takes one a tick,
+/// so this only fills if keys are mashed faster than the game ticks.
+const CAPACITY: usize = 8;
+
+/// How long each gesture takes.
+#[derive(Clone, Copy)]
+pub struct Timing {
+    /// How long a key has to be held to count as a long press.
+    pub long_press: Duration,
+    /// How soon a key has to be pressed again to count as a double tap.
+    pub double_tap: Duration,
+    /// How soon after one key of a chord another has to go down. Presses of
+    /// keys that are part of a chord are held back this long, so a chord
+    /// pressed within it never plays a move. Longer makes moves on those keys
+    /// lag more. A chord pressed more slowly still counts once all its keys
+    /// are held, but the keys let through first have played their moves.
+    pub chord_window: Duration,
+    /// How long a chord has to be held to count.
+    pub chord_hold: Duration,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            long_press: Duration::from_millis(800),
+            double_tap: Duration::from_millis(300),
+            chord_window: Duration::from_millis(200),
+            chord_hold: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Keys held down together.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Chord {
+    /// The top left and bottom right corners.
+    Diagonal,
+    FourCorners,
+}
+
+impl Chord {
+    const ALL: [Self; 2] = [Self::Diagonal, Self::FourCorners];
+
+    fn keys<const W: usize, const H: usize>(self) -> Board<bool, W, H> {
+        from_fn(|y| {
+            from_fn(|x| {
+                let top_left = x == 0 && y == 0;
+                let bottom_right = x == W - 1 && y == H - 1;
+                let other_corners = (x == 0 && y == H - 1) || (x == W - 1 && y == 0);
+                match self {
+                    Self::Diagonal => top_left || bottom_right,
+                    Self::FourCorners => top_left || bottom_right || other_corners,
+                }
+            })
+        })
+    }
+
+    fn contains<const W: usize, const H: usize>(self, position: Position) -> bool {
+        self.keys::<W, H>()[position.y][position.x]
+    }
+}
+
+/// Passes the debounced events on, adding [`Event::LongPress`],
+/// [`Event::DoubleTap`] and [`Event::Chord`] where they happen.
+pub struct Gestures<const W: usize, const H: usize> {
+    keys: Debouncer<W, H>,
+    timing: Timing,
+    queue: Queue<Event, CAPACITY>,
+    /// A press of a chord key, held back in case it starts a chord.
+    pending: Option<(Position, Instant)>,
+    /// Whether a chord was started and not every key has been let go since.
+    chording: bool,
+    /// The chord held down, since when, and whether it has been sent.
+    chord: Option<(Chord, Instant, bool)>,
+    /// The last key pressed, while it is held and not yet a long press.
+    down: Option<(Position, Instant)>,
+    /// The last key pressed and when, for spotting double taps.
+    tapped: Option<(Position, Instant)>,
+}
+
+impl<const W: usize, const H: usize> Gestures<W, H> {
+    pub fn new(timing: Timing) -> Self {
+        Self {
+            keys: Debouncer::new(),
+            timing,
+            queue: Queue::new(),
+            pending: None,
+            chording: false,
+            chord: None,
+            down: None,
+            tapped: None,
+        }
+    }
+
+    /// Queues whatever happened since the last reading, `scan` being taken at
+    /// `now`.
+    pub fn update(&mut self, scan: ButtonScan<W, H>, now: Instant) {
+        // Four corners read the same as three with a phantom fourth, and
+        // either is near enough to holding the chord.
+        if scan == ButtonScan::new(Chord::FourCorners.keys()) {
+            self.keys.update_trusted(scan, now);
+        } else {
+            self.keys.update(scan, now);
+        }
+        while let Some(event) = self.keys.pop() {
+            self.handle(event, now);
+        }
+        if !self.chording
+            && Chord::ALL
+                .into_iter()
+                .any(|chord| chord.keys() == self.keys.held())
+        {
+            // The keys came down too far apart to be held back.
+            self.chording = true;
+            self.pending = None;
+            self.down = None;
+        }
+
+        if let Some((position, since)) = self.pending {
+            if now - since >= self.timing.chord_window {
+                self.pending = None;
+                self.press(position, since);
+            }
+        }
+        if let Some((position, since)) = self.down {
+            if now - since >= self.timing.long_press {
+                self.down = None;
+                self.queue.push(Event::LongPress(position));
+            }
+        }
+        if self.chording {
+            self.update_chord(now);
+        }
+    }
+
+    fn handle(&mut self, event: Event, now: Instant) {
+        if self.chording {
+            return;
+        }
+        match event {
+            Event::Press(position)
+                if Chord::ALL
+                    .iter()
+                    .any(|chord| chord.contains::<W, H>(position)) =>
+            {
+                if self.pending.take().is_some() {
+                    self.chording = true;
+                    self.down = None;
+                } else {
+                    self.pending = Some((position, now));
+                }
+            }
+            Event::Press(position) => self.press(position, now),
+            Event::Release(position) => {
+                if let Some((_, since)) = self.pending.filter(|(pending, _)| *pending == position) {
+                    self.pending = None;
+                    self.press(position, since);
+                }
+                if self.down.is_some_and(|(down, _)| down == position) {
+                    self.down = None;
+                }
+                self.queue.push(event);
+            }
+            Event::Repeat(position)
+                if self.pending.is_some_and(|(pending, _)| pending == position) => {}
+            _ => self.queue.push(event),
+        }
+    }
+
+    /// Passes a press on, `since` being when it happened.
+    fn press(&mut self, position: Position, since: Instant) {
+        self.queue.push(Event::Press(position));
+        self.down = Some((position, since));
+        match self.tapped {
+            Some((tapped, at)) if tapped == position && since - at <= self.timing.double_tap => {
+                self.tapped = None;
+                self.queue.push(Event::DoubleTap(position));
+            }
+            _ => self.tapped = Some((position, since)),
+        }
+    }
+
+    fn update_chord(&mut self, now: Instant) {
+        let held = self.keys.held();
+        if held.iter().flatten().all(|pressed| !pressed) {
+            self.chording = false;
+            self.chord = None;
+            return;
+        }
+        let current = Chord::ALL.into_iter().find(|chord| chord.keys() == held);
+        match (current, self.chord) {
+            (Some(chord), Some((tracked, since, false)))
+                if chord == tracked && now - since >= self.timing.chord_hold =>
+            {
+                self.chord = Some((chord, since, true));
+                self.queue.push(Event::Chord(chord));
+            }
+            (Some(chord), Some((tracked, ..))) if chord == tracked => {}
+            (current, _) => self.chord = current.map(|chord| (chord, now, false)),
+        }
+    }
+
+    /// The next key event or gesture, in the order the player made them.
+    pub fn pop(&mut self) -> Option<Event> {
+        self.queue.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chord, Gestures, Timing};
+    use crate::{
+        io::Event,
+        position::pos,
+        testing::{hold, scan},
+    };
+
+    #[test]
+    fn tap_and_long_press() {
+        let mut gestures = Gestures::new(Timing::default());
+        let centre = pos(1, 1);
+        assert_eq!(
+            hold(&mut gestures, &[centre], 0, 100),
+            [Event::Press(centre)]
+        );
+        assert_eq!(hold(&mut gestures, &[], 100, 150), [Event::Release(centre)]);
+        assert_eq!(
+            hold(&mut gestures, &[centre], 150, 250),
+            [Event::Press(centre), Event::DoubleTap(centre)]
+        );
+        let events = hold(&mut gestures, &[centre], 250, 1000);
+        assert!(events.contains(&Event::LongPress(centre)));
+    }
+
+    #[test]
+    fn corner_tap_is_held_back_then_passed_on() {
+        let mut gestures = Gestures::new(Timing::default());
+        assert_eq!(hold(&mut gestures, &[pos(0, 0)], 0, 50), []);
+        assert_eq!(
+            hold(&mut gestures, &[pos(0, 0)], 50, 300),
+            [Event::Press(pos(0, 0))]
+        );
+    }
+
+    #[test]
+    fn diagonal_chord() {
+        let mut gestures = Gestures::new(Timing::default());
+        let diagonal = [pos(0, 0), pos(2, 2)];
+        assert_eq!(hold(&mut gestures, &diagonal, 0, 1000), []);
+        assert_eq!(
+            hold(&mut gestures, &diagonal, 1000, 1100),
+            [Event::Chord(Chord::Diagonal)]
+        );
+        // Letting go afterwards plays nothing.
+        assert_eq!(hold(&mut gestures, &[], 1100, 1200), []);
+    }
+
+    #[test]
+    fn diagonal_chord_pressed_slowly() {
+        let mut gestures = Gestures::new(Timing::default());
+        let diagonal = [pos(0, 0), pos(2, 2)];
+        assert_eq!(hold(&mut gestures, &diagonal[..1], 0, 150), []);
+        assert_eq!(hold(&mut gestures, &diagonal, 150, 1100), []);
+        assert_eq!(
+            hold(&mut gestures, &diagonal, 1100, 1300),
+            [Event::Chord(Chord::Diagonal)]
+        );
+    }
+
+    #[test]
+    fn diagonal_chord_pressed_too_slowly_to_hold_back() {
+        let mut gestures = Gestures::new(Timing::default());
+        let diagonal = [pos(0, 0), pos(2, 2)];
+        assert_eq!(
+            hold(&mut gestures, &diagonal[..1], 0, 450),
+            [Event::Press(pos(0, 0))]
+        );
+        assert_eq!(hold(&mut gestures, &diagonal, 450, 1400), []);
+        assert_eq!(
+            hold(&mut gestures, &diagonal, 1400, 1500),
+            [Event::Chord(Chord::Diagonal)]
+        );
+        assert_eq!(hold(&mut gestures, &[], 1500, 1600), []);
+    }
+
+    #[test]
+    fn four_corners_through_the_phantom_filter() {
+        let mut gestures = Gestures::new(Timing::default());
+        let corners = [pos(0, 0), pos(2, 0), pos(0, 2), pos(2, 2)];
+        assert!(!scan(&corners).trustworthy());
+        let events = hold(&mut gestures, &corners, 0, 1200);
+        assert_eq!(events, [Event::Chord(Chord::FourCorners)]);
+    }
+
+    #[test]
+    fn other_rectangles_are_still_filtered() {
+        let mut gestures = Gestures::new(Timing::default());
+        let rectangle = [pos(0, 0), pos(1, 0), pos(0, 1), pos(1, 1)];
+        assert_eq!(hold(&mut gestures, &rectangle, 0, 1200), []);
+    }
+}
@@ -3,33 +3,44 @@ use core::iter::Flatten;
 
 use crate::board::Board;
 use crate::position::pos;
-use crate::{buttons::Buttons, display::Display, millis::millis, position::Position};
+use crate::position::Position;
 
-use arduino_hal::{pins, Peripherals};
 use itertools::Itertools;
 
 use crate::{
-    buttons::buttons,
-    consts::SIZE,
-    display::{display, DisplayPixel},
-    game::Game,
-    player::{choose, Player},
+    animation::{self, Animation, Effect, Playback},
+    display::DisplayPixel,
+    game::{Game, Tick},
+    io::{Event, Input},
+    player::Player,
+    rules::{Rules, Status},
+    time::{Duration, Instant},
+    transition::Transition,
+    turn::Turns,
 };
 
-pub struct Hexapawn {
-    pawns: Pawns,
-    current_player: Player,
-    state: GameState,
-    display: Display,
-    buttons: Buttons,
+/// Brightness of the previews of where a picked pawn can go.
+const GHOST: u8 = DisplayPixel::MAX / 4;
+
+pub struct Hexapawn<const W: usize, const H: usize> {
+    turns: Turns<Grid<W, H>, GameState, W, H>,
 }
 
-#[derive(Clone, Copy)]
-struct Pawns([Option<Pawn>; 6]);
+/// One row of pawns per player, red first. Each row is kept in order of
+/// position, so a position lists its moves in the same order however it was
+/// reached, as a [`crate::search::Search`] relies on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Pawns<const W: usize, const H: usize>([[Option<Pawn>; W]; 2]);
+
+impl<const W: usize, const H: usize> Pawns<W, H> {
+    fn sort(&mut self) {
+        for row in &mut self.0 {
+            row.sort_unstable_by_key(|slot| slot.map(|pawn| (pawn.position.y, pawn.position.x)));
+        }
+    }
 
-impl Pawns {
-    fn into_display_buffer(self) -> Board<DisplayPixel> {
-        let mut buffer = [[DisplayPixel::default(); SIZE]; SIZE];
+    fn into_display_buffer(self) -> Board<DisplayPixel, W, H> {
+        let mut buffer = [[DisplayPixel::default(); W]; H];
         for pawn in self {
             buffer[pawn.position.y][pawn.position.x] = DisplayPixel::from(pawn.player);
         }
@@ -37,11 +48,11 @@ impl Pawns {
     }
 
     fn valid_next_moves(self, pawn: Pawn) -> impl Iterator<Item = Position> {
-        let forward = pawn.position.offset_y(match pawn.player {
+        let forward = pawn.position.offset_y::<H>(match pawn.player {
             Player::Red => 1,
             Player::Blue => -1,
         });
-        let sides = [-1, 1].map(|delta| forward.and_then(|forward| forward.offset_x(delta)));
+        let sides = [-1, 1].map(|delta| forward.and_then(|forward| forward.offset_x::<W>(delta)));
         forward
             .filter(|forward| !self.into_iter().any(|other| other.position == *forward))
             .into_iter()
@@ -50,14 +61,28 @@ impl Pawns {
                     .any(|other| other.player != pawn.player && other.position == *side)
             }))
     }
+
+    fn slot(&mut self, position: Position) -> Option<&mut Option<Pawn>> {
+        self.0
+            .as_flattened_mut()
+            .iter_mut()
+            .find(|slot| slot.is_some_and(|pawn| pawn.position == position))
+    }
+}
+
+const fn row(player: Player) -> usize {
+    match player {
+        Player::Red => 0,
+        Player::Blue => 1,
+    }
 }
 
-impl IntoIterator for Pawns {
+impl<const W: usize, const H: usize> IntoIterator for Pawns<W, H> {
     type Item = Pawn;
-    type IntoIter = Flatten<IntoIter<Option<Pawn>, 6>>;
+    type IntoIter = Flatten<Flatten<IntoIter<[Option<Pawn>; W], 2>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter().flatten()
+        self.0.into_iter().flatten().flatten()
     }
 }
 
@@ -79,108 +104,161 @@ enum GameState {
     PresentCurrentPlayer,
     WaitForPick,
     WaitForPlace(Pawn),
-    DisplayWinner(Player),
+    /// The winner's colour, shown until reset.
+    DisplayWinner,
 }
 
-impl Hexapawn {
-    pub fn new() -> Self {
-        let peripherals = Peripherals::take().unwrap();
-        let pins = pins!(peripherals);
+impl<const W: usize, const H: usize> Hexapawn<W, H> {
+    /// The home rows have to be distinct for the game to make sense.
+    const SUPPORTED: () = assert!(H >= 2, "hexapawn needs at least two rows");
 
-        let current_player = choose!(peripherals, pins);
+    /// A game `current_player` starts, with `computer` playing one side or
+    /// `None` for two players.
+    pub fn new(current_player: Player, computer: Option<Player>) -> Self {
+        let () = Self::SUPPORTED;
         Self {
-            pawns: Pawns([
-                Some(Pawn::new(pos(0, 0), Player::Red)),
-                Some(Pawn::new(pos(1, 0), Player::Red)),
-                Some(Pawn::new(pos(2, 0), Player::Red)),
-                Some(Pawn::new(pos(0, SIZE - 1), Player::Blue)),
-                Some(Pawn::new(pos(1, SIZE - 1), Player::Blue)),
-                Some(Pawn::new(pos(2, SIZE - 1), Player::Blue)),
-            ]),
-            current_player,
-            state: GameState::default(),
-            display: display!(pins, [[DisplayPixel::from(current_player); SIZE]; SIZE]),
-            buttons: buttons!(pins),
+            turns: Turns::new(Grid::new(current_player), computer),
         }
     }
+}
+
+/// Any board with at least two rows, starting with a full home row each.
+impl<const W: usize, const H: usize> Game<W, H> for Hexapawn<W, H> {
+    type Rules = Grid<W, H>;
 
-    fn run(mut self) -> ! {
-        loop {
-            match self.state {
-                GameState::PresentCurrentPlayer => {
-                    if millis() > 1000 {
-                        self.state = GameState::WaitForPick;
+    fn tick(&mut self, input: &Input, now: Instant) -> Tick<W, H> {
+        let turns = &mut self.turns;
+        if let Some(Event::Undo) = input.event {
+            // A pawn picked but not yet moved is just put down.
+            if let GameState::WaitForPlace(_) = turns.state {
+                turns.enter(GameState::WaitForPick);
+                turns.animation = None;
+            } else {
+                turns.undo(GameState::WaitForPick, Transition::Wipe);
+            }
+        }
+        let frame = match turns.state {
+            GameState::PresentCurrentPlayer => {
+                turns.present(now, GameState::WaitForPick, Transition::Wipe)
+            }
+            GameState::WaitForPick => {
+                let buffer = turns.grid.pawns.into_display_buffer();
+                if let Some(Event::Press(Position { x, y })) =
+                    input.event.filter(|_| !turns.computers_turn())
+                {
+                    if let Ok(pawn) = turns
+                        .grid
+                        .pawns
+                        .into_iter()
+                        .filter(|pawn| *pawn == Pawn::new(pos(x, y), turns.grid.current_player))
+                        .exactly_one()
+                    {
+                        select(turns, pawn);
                     }
                 }
-                GameState::WaitForPick => {
-                    self.display.write(self.pawns.into_display_buffer());
-                    if let Some(Position { x, y }) = self.buttons.scan().exactly_one() {
-                        if let Ok(pawn) = self
-                            .pawns
-                            .into_iter()
-                            .filter(|pawn| *pawn == Pawn::new(pos(x, y), self.current_player))
-                            .exactly_one()
-                        {
-                            self.state = GameState::WaitForPlace(pawn);
-                        }
-                    }
-                    if let Some(winner) = self.winner() {
-                        self.state = GameState::DisplayWinner(winner);
-                    }
+                if let Status::Won(winner) = turns.grid.status() {
+                    display_winner(turns, winner);
                 }
-                GameState::WaitForPlace(pawn) => {
-                    let mut buffer = self.pawns.into_display_buffer();
-                    buffer[pawn.position.y][pawn.position.x].filter(millis() % 1000 < 500);
-                    for position in self.pawns.valid_next_moves(pawn) {
-                        if millis() % 1000 > 500 {
-                            buffer[position.y][position.x].or_player(pawn.player);
-                        }
-                    }
-                    self.display.write(buffer);
-                    if let Some(new) = self.buttons.scan().exactly_one() {
-                        if let Ok(pawn) = self
-                            .pawns
-                            .into_iter()
-                            .filter(|pawn| *pawn == Pawn::new(new, self.current_player))
-                            .exactly_one()
-                        {
-                            self.state = GameState::WaitForPlace(pawn);
-                        } else if self
-                            .pawns
-                            .valid_next_moves(pawn)
-                            .any(|position| position == new)
-                        {
-                            for other in &mut self.pawns.0 {
-                                if other.is_some_and(|other| other.position == new) {
-                                    *other = None;
-                                }
-                            }
-
-                            self.pawns
-                                .0
-                                .iter_mut()
-                                .flatten()
-                                .filter(|other| other.position == pawn.position)
-                                .exactly_one()
-                                .ok()
-                                .unwrap()
-                                .position = new;
-                            self.current_player.flip();
-                            self.state = GameState::WaitForPick;
-                        }
-                    }
+                buffer
+            }
+            GameState::WaitForPlace(pawn) => {
+                let mut buffer = turns.grid.pawns.into_display_buffer();
+                for position in turns.grid.pawns.valid_next_moves(pawn) {
+                    let cell = &mut buffer[position.y][position.x];
+                    *cell = cell.mix(DisplayPixel::from(pawn.player).scale(GHOST));
                 }
-                GameState::DisplayWinner(winner) => {
-                    let mut pixels = from_fn::<_, { SIZE * SIZE }, _>(|index| {
-                        DisplayPixel::from(winner)
-                            .and(index % 2 == usize::from(millis() % 1000 < 500))
-                    })
-                    .into_iter();
-                    self.display
-                        .write(from_fn(|_| from_fn(|_| pixels.next().unwrap())));
+                if let Some(Event::Press(new)) = input.event {
+                    if let Ok(pawn) = turns
+                        .grid
+                        .pawns
+                        .into_iter()
+                        .filter(|pawn| *pawn == Pawn::new(new, turns.grid.current_player))
+                        .exactly_one()
+                    {
+                        select(turns, pawn);
+                    } else if let Some(mv) = turns
+                        .grid
+                        .legal_moves()
+                        .find(|mv| mv.from == pawn.position && mv.to == new)
+                    {
+                        turns.play(mv, GameState::WaitForPick);
+                    }
                 }
+                buffer
             }
-            self.display.show();
+            GameState::DisplayWinner => [[DisplayPixel::default(); W]; H],
+        };
+        turns.tick(frame, now)
+    }
+
+    fn wants(&self, event: Event) -> bool {
+        matches!(event, Event::Press(_) | Event::Undo)
+    }
+
+    fn think(&self) -> Option<Grid<W, H>> {
+        self.turns
+            .think(matches!(self.turns.state, GameState::WaitForPick))
+    }
+
+    fn computer_move(&mut self, mv: Move) {
+        self.turns
+            .play_computer(mv, GameState::WaitForPick, animation::cell(mv.to));
+    }
+}
+
+/// Blinks `pawn` while waiting for where it should go.
+fn select<const W: usize, const H: usize>(
+    turns: &mut Turns<Grid<W, H>, GameState, W, H>,
+    pawn: Pawn,
+) {
+    turns.enter(GameState::WaitForPlace(pawn));
+    turns.animation = Some(Animation::new(
+        Effect::Blink {
+            cells: animation::cell(pawn.position),
+        },
+        Duration::from_secs(1),
+        Playback::Loop,
+    ));
+}
+
+fn display_winner<const W: usize, const H: usize>(
+    turns: &mut Turns<Grid<W, H>, GameState, W, H>,
+    winner: Player,
+) {
+    turns.enter(GameState::DisplayWinner);
+    turns.transition = Some(Transition::Spiral);
+    turns.animation = Some(Animation::new(
+        Effect::Checkerboard {
+            on: winner.into(),
+            off: DisplayPixel::default(),
+        },
+        Duration::from_secs(1),
+        Playback::Loop,
+    ));
+}
+
+/// Where the pawns are, and whose turn it is.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Grid<const W: usize, const H: usize> {
+    pawns: Pawns<W, H>,
+    current_player: Player,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    capture: bool,
+}
+
+impl<const W: usize, const H: usize> Grid<W, H> {
+    pub fn new(current_player: Player) -> Self {
+        Self {
+            pawns: Pawns([
+                from_fn(|x| Some(Pawn::new(pos(x, 0), Player::Red))),
+                from_fn(|x| Some(Pawn::new(pos(x, H - 1), Player::Blue))),
+            ]),
+            current_player,
         }
     }
 
@@ -208,7 +286,7 @@ impl Hexapawn {
             .into_iter()
             .filter(|pawn| {
                 (match pawn.player {
-                    Player::Red => SIZE - 1,
+                    Player::Red => H - 1,
                     Player::Blue => 0,
                 }) == pawn.position.y
             })
@@ -220,8 +298,144 @@ impl Hexapawn {
     }
 }
 
-impl Game for Hexapawn {
-    fn play() -> ! {
-        Self::new().run();
+impl<const W: usize, const H: usize> Rules for Grid<W, H> {
+    type Move = Move;
+    type Moves = Flatten<Flatten<IntoIter<[Option<Move>; 3], W>>>;
+
+    /// As far as helps on 3x3.
+    const LOOKAHEAD: u8 = 4;
+
+    fn side_to_move(&self) -> Player {
+        self.current_player
+    }
+
+    fn legal_moves(&self) -> Self::Moves {
+        let pawns = self.pawns;
+        pawns.0[row(self.current_player)]
+            .map(|pawn| {
+                let mut moves = pawn.into_iter().flat_map(|pawn| {
+                    pawns.valid_next_moves(pawn).map(move |to| Move {
+                        from: pawn.position,
+                        to,
+                        capture: pawns.into_iter().any(|other| other.position == to),
+                    })
+                });
+                from_fn(|_| moves.next())
+            })
+            .into_iter()
+            .flatten()
+            .flatten()
+    }
+
+    fn apply(&mut self, mv: Move) {
+        if mv.capture {
+            if let Some(captured) = self.pawns.slot(mv.to) {
+                *captured = None;
+            }
+        }
+        if let Some(Some(pawn)) = self.pawns.slot(mv.from) {
+            pawn.position = mv.to;
+        }
+        self.pawns.sort();
+        self.current_player.flip();
+    }
+
+    fn undo(&mut self, mv: Move) {
+        self.current_player.flip();
+        if let Some(Some(pawn)) = self.pawns.slot(mv.to) {
+            pawn.position = mv.from;
+        }
+        if mv.capture {
+            let mut opponent = self.current_player;
+            opponent.flip();
+            if let Some(slot) = self.pawns.0[row(opponent)]
+                .iter_mut()
+                .find(|slot| slot.is_none())
+            {
+                *slot = Some(Pawn::new(mv.to, opponent));
+            }
+        }
+        self.pawns.sort();
+    }
+
+    fn status(&self) -> Status {
+        self.winner().map_or(Status::Ongoing, Status::Won)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, Move};
+    use crate::{
+        player::Player,
+        position::pos,
+        rules::{Rules, Status},
+        search::best_move,
+        testing::losses,
+    };
+
+    /// The move from `from` to `to` in `grid`.
+    fn mv(grid: &Grid<3, 3>, from: (usize, usize), to: (usize, usize)) -> Move {
+        grid.legal_moves()
+            .find(|mv| mv.from == pos(from.0, from.1) && mv.to == pos(to.0, to.1))
+            .unwrap()
+    }
+
+    /// Checks that every move from `grid`, and every move after it, is taken
+    /// back exactly, returning how many captures there were.
+    fn round_trips(grid: &mut Grid<3, 3>) -> u32 {
+        if grid.status() != Status::Ongoing {
+            return 0;
+        }
+        let start = *grid;
+        let mut captures = 0;
+        for mv in start.legal_moves() {
+            grid.apply(mv);
+            assert!(grid.side_to_move() != start.side_to_move());
+            captures += u32::from(mv.capture) + round_trips(grid);
+            grid.undo(mv);
+            assert!(*grid == start);
+        }
+        captures
+    }
+
+    #[test]
+    fn apply_and_undo_round_trip() {
+        assert!(round_trips(&mut Grid::new(Player::Red)) > 0);
+    }
+
+    #[test]
+    fn reaching_the_far_row_wins() {
+        let mut grid = Grid::<3, 3>::new(Player::Red);
+        grid.apply(mv(&grid, (0, 0), (0, 1)));
+        grid.apply(mv(&grid, (2, 2), (2, 1)));
+        assert!(grid.status() == Status::Ongoing);
+        grid.apply(mv(&grid, (0, 1), (1, 2)));
+        assert!(grid.status() == Status::Won(Player::Red));
+    }
+
+    #[test]
+    fn takes_a_win() {
+        let mut grid = Grid::<3, 3>::new(Player::Red);
+        grid.apply(mv(&grid, (0, 0), (0, 1)));
+        grid.apply(mv(&grid, (2, 2), (2, 1)));
+        let best = best_move(grid, 4).unwrap();
+        assert_eq!((best.from, best.to), (pos(0, 1), pos(1, 2)));
+    }
+
+    #[test]
+    fn stops_a_pawn_getting_through() {
+        // Red's pawn in the middle takes the blue corner next unless blue
+        // takes it first.
+        let mut grid = Grid::<3, 3>::new(Player::Red);
+        grid.apply(mv(&grid, (1, 0), (1, 1)));
+        let best = best_move(grid, 4).unwrap();
+        assert_eq!(best.to, pos(1, 1));
+    }
+
+    /// 3x3 hexapawn is a win for whoever goes second.
+    #[test]
+    fn never_loses_going_second() {
+        assert_eq!(losses(&mut Grid::<3, 3>::new(Player::Red), Player::Blue), 0);
     }
 }
@@ -0,0 +1,52 @@
+/// The moves of a game so far, for taking them back. Once full the oldest move
+/// is forgotten, so only the latest `N` can be taken back.
+pub struct History<T, const N: usize> {
+    items: [Option<T>; N],
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> History<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            items: [None; N],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.len == N {
+            self.items.copy_within(1.., 0);
+            self.len -= 1;
+        }
+        self.items[self.len] = Some(item);
+        self.len += 1;
+    }
+
+    /// The latest move still remembered.
+    pub fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        self.items[self.len].take()
+    }
+}
+
+impl<T: Copy, const N: usize> Default for History<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+
+    #[test]
+    fn forgets_the_oldest_once_full() {
+        let mut history = History::<u8, 2>::new();
+        history.push(1);
+        history.push(2);
+        history.push(3);
+        assert_eq!(history.pop(), Some(3));
+        assert_eq!(history.pop(), Some(2));
+        assert_eq!(history.pop(), None);
+    }
+}
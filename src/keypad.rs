@@ -0,0 +1,52 @@
+//! Moves typed over a serial port instead of pressed on the keys. The digits
+//! stand for the keys in numpad layout, so on a 3x3 matrix `7` is the top left
+//! key and `3` the bottom right. `u` takes back the last move and `r` ends the
+//! game.
+
+use crate::{io::Event, position::pos, queue::Queue};
+
+/// Events typed but not yet read. A digit queues a press and a release, so
+/// this holds two moves typed between ticks.
+const CAPACITY: usize = 4;
+
+/// Turns bytes typed into events.
+pub struct Keypad<const W: usize, const H: usize> {
+    queue: Queue<Event, CAPACITY>,
+}
+
+impl<const W: usize, const H: usize> Keypad<W, H> {
+    pub const fn new() -> Self {
+        Self {
+            queue: Queue::new(),
+        }
+    }
+
+    /// Queues what `byte` stands for, if anything. A digit presses and lets go
+    /// of its key at once.
+    pub fn feed(&mut self, byte: u8) {
+        match byte.to_ascii_lowercase() {
+            b'1'..=b'9' => {
+                let index = usize::from(byte - b'1');
+                if index < W * H {
+                    let position = pos(index % W, H - 1 - index / W);
+                    self.queue.push(Event::Press(position));
+                    self.queue.push(Event::Release(position));
+                }
+            }
+            b'u' => self.queue.push(Event::Undo),
+            b'r' => self.queue.push(Event::Reset),
+            _ => {}
+        }
+    }
+
+    /// The next event typed, in the order the bytes arrived.
+    pub fn pop(&mut self) -> Option<Event> {
+        self.queue.pop()
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Keypad<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,295 @@
+#![cfg_attr(not(test), no_std)]
+
+pub mod animation;
+pub mod debounce;
+pub mod display;
+pub mod gesture;
+pub mod hexapawn;
+pub mod history;
+pub mod keypad;
+pub mod orientation;
+pub mod queue;
+pub mod random;
+pub mod recording;
+pub mod runtime;
+pub mod scan;
+pub mod scheduler;
+pub mod search;
+pub mod settings;
+#[cfg(test)]
+mod testing;
+pub mod text;
+pub mod time;
+pub mod transition;
+pub mod ttt;
+pub mod turn;
+
+pub mod board {
+    pub type Board<C, const W: usize, const H: usize> = [[C; W]; H];
+}
+
+pub mod player {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum Player {
+        Red,
+        Blue,
+    }
+
+    impl Player {
+        /// Who starts a game played from `seed`.
+        pub const fn from_seed(seed: u16) -> Self {
+            if seed & 1 == 0 {
+                Self::Red
+            } else {
+                Self::Blue
+            }
+        }
+
+        pub fn flip(&mut self) {
+            *self = match self {
+                Self::Red => Self::Blue,
+                Self::Blue => Self::Red,
+            }
+        }
+    }
+}
+
+pub mod game {
+    use crate::{
+        board::Board,
+        display::DisplayPixel,
+        io::{Event, Input},
+        rules::Rules,
+        time::Instant,
+        transition::Transition,
+    };
+
+    pub enum Tick<const W: usize, const H: usize> {
+        Frame(Board<DisplayPixel, W, H>),
+        /// Like [`Self::Frame`], but eased in from whatever was shown before.
+        /// Frames from the following ticks keep the transition going until it
+        /// is over.
+        Transition(Board<DisplayPixel, W, H>, Transition),
+        /// Hand control back to whatever started the game.
+        Exit,
+    }
+
+    /// Implemented for every `W`x`H` matrix a game can be played on.
+    pub trait Game<const W: usize, const H: usize> {
+        /// The position the computer searches when it plays a side, or
+        /// [`crate::rules::NoRules`] for games it doesn't.
+        type Rules: Rules + Copy + PartialEq;
+
+        /// Advances the game by one step, `now` counting from
+        /// [`Instant::ZERO`] when the game started.
+        fn tick(&mut self, input: &Input, now: Instant) -> Tick<W, H>;
+
+        /// Whether each frame has to reach the display before the next tick,
+        /// for fast animations that mustn't drop frames.
+        fn synced(&self) -> bool {
+            false
+        }
+
+        /// Whether `event` can make any difference to the game, so recordings
+        /// can leave out the rest.
+        fn wants(&self, _event: Event) -> bool {
+            true
+        }
+
+        /// The position to find the computer a move from, while it is the
+        /// computer's turn.
+        fn think(&self) -> Option<Self::Rules> {
+            None
+        }
+
+        /// Plays the computer's move, found from the position last returned
+        /// by [`Self::think`]. The runtime decides when, so the move can be
+        /// followed and comes at the same time when played back.
+        fn computer_move(&mut self, _mv: <Self::Rules as Rules>::Move) {}
+    }
+}
+
+pub mod rules {
+    use crate::player::Player;
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub enum Status {
+        Ongoing,
+        Won(Player),
+        Draw,
+    }
+
+    /// The rules of a two-player, turn-based game, as needed by [`crate::search`].
+    pub trait Rules {
+        type Move: Copy + PartialEq;
+        /// Made afresh for every move a search looks into, so it should be
+        /// cheap to make.
+        type Moves: IntoIterator<Item = Self::Move>;
+
+        /// Plies the computer looks ahead when it plays.
+        const LOOKAHEAD: u8 = 4;
+
+        fn side_to_move(&self) -> Player;
+
+        /// Only meaningful while the game is [`Status::Ongoing`].
+        fn legal_moves(&self) -> Self::Moves;
+
+        fn apply(&mut self, mv: Self::Move);
+
+        /// Reverts `mv`, which must be the last move applied.
+        fn undo(&mut self, mv: Self::Move);
+
+        fn status(&self) -> Status;
+
+        /// How good an ongoing position is for the side to move, for when a
+        /// search runs out of depth.
+        fn evaluate(&self) -> i16 {
+            0
+        }
+    }
+
+    /// The rules of a game the computer never plays, which can't be made.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub enum NoRules {}
+
+    impl Rules for NoRules {
+        type Move = ();
+        type Moves = core::iter::Empty<()>;
+
+        fn side_to_move(&self) -> Player {
+            match *self {}
+        }
+
+        fn legal_moves(&self) -> Self::Moves {
+            match *self {}
+        }
+
+        fn apply(&mut self, _mv: ()) {
+            match *self {}
+        }
+
+        fn undo(&mut self, _mv: ()) {
+            match *self {}
+        }
+
+        fn status(&self) -> Status {
+            match *self {}
+        }
+    }
+}
+
+pub mod io {
+    use crate::{
+        board::Board, display::DisplayPixel, gesture::Chord, position::Position, scan::ButtonScan,
+        time::Instant,
+    };
+
+    /// Something a game can draw whole frames to.
+    pub trait FrameSink<const W: usize, const H: usize> {
+        fn write(&mut self, buffer: Board<DisplayPixel, W, H>);
+
+        /// Called once per runtime loop pass, for sinks that aren't refreshed
+        /// in the background.
+        fn show(&mut self) {}
+
+        /// Blocks until the last frame written is being shown, for sinks that
+        /// swap frames in the background.
+        fn wait_for_swap(&mut self) {}
+
+        /// Scales every pixel written from now on to `level` out of
+        /// [`DisplayPixel::MAX`].
+        fn set_brightness(&mut self, level: u8);
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Event {
+        Press(Position),
+        Release(Position),
+        /// Sent every so often while a key stays held down.
+        Repeat(Position),
+        /// Sent once while a key stays held down for a while, after its
+        /// [`Self::Press`].
+        LongPress(Position),
+        /// Sent after the [`Self::Press`] of a key pressed twice in quick
+        /// succession.
+        DoubleTap(Position),
+        /// Keys held down together. The keys' own events aren't sent.
+        Chord(Chord),
+        /// Takes back the last move.
+        Undo,
+        /// Ends the game, as holding all four corners does.
+        Reset,
+        /// The computer's move, by its place among the legal moves. Only
+        /// recordings hold it, and the runtime plays it rather than the game.
+        Computer(u8),
+    }
+
+    /// The next thing the player did, kept queued until the game ticks.
+    #[derive(Clone, Copy)]
+    pub struct Input {
+        pub event: Option<Event>,
+    }
+
+    /// Something the state of the keys can be read from.
+    pub trait InputSource<const W: usize, const H: usize> {
+        /// Which keys read as pressed right now, bounce and all.
+        fn read(&mut self) -> ButtonScan<W, H>;
+
+        /// Waits, using as little power as it can, until a key is pressed.
+        fn sleep(&mut self) {}
+
+        /// The next event from somewhere other than the keys, such as moves
+        /// typed over a serial port.
+        fn event(&mut self) -> Option<Event> {
+            None
+        }
+    }
+
+    /// Time since some fixed point, usually boot.
+    pub trait Clock {
+        fn now(&self) -> Instant;
+
+        /// Idles until the time may have moved on, for when there is nothing
+        /// to do until then.
+        fn wait(&self) {}
+    }
+
+    /// A few bytes that survive power cycles, such as the EEPROM.
+    pub trait Storage {
+        fn read(&mut self, offset: u16, buffer: &mut [u8]);
+
+        fn write(&mut self, offset: u16, bytes: &[u8]);
+    }
+}
+
+pub mod position {
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct Position {
+        pub x: usize,
+        pub y: usize,
+    }
+
+    pub const fn pos(x: usize, y: usize) -> Position {
+        Position { x, y }
+    }
+
+    impl Position {
+        pub fn offset_y<const H: usize>(self, delta: isize) -> Option<Self> {
+            let y = self.y.checked_add_signed(delta)?;
+            if y < H {
+                Some(pos(self.x, y))
+            } else {
+                None
+            }
+        }
+
+        pub fn offset_x<const W: usize>(self, delta: isize) -> Option<Self> {
+            let x = self.x.checked_add_signed(delta)?;
+            if x < W {
+                Some(pos(x, self.y))
+            } else {
+                None
+            }
+        }
+    }
+}
@@ -1,113 +1,116 @@
-#![feature(abi_avr_interrupt, array_chunks)]
+#![feature(abi_avr_interrupt, array_chunks, asm_experimental_arch)]
 #![no_std]
 #![no_main]
 
-use crate::game::Game;
-use arduino_hal::Peripherals;
-use hexapawn::Hexapawn;
-use millis::init;
-use panic_halt as _;
-use ttt::TicTacToe;
+#[cfg(feature = "ldr")]
+use crate::avr::ambient::AutoDim;
+use crate::{
+    avr::{
+        buttons::buttons,
+        diagnostics,
+        display::display,
+        keypad::SerialKeypad,
+        millis::{init, Timer0},
+        panic::Location,
+        storage::EepromStorage,
+        watchdog::Watched,
+    },
+    consts::{COMPUTER, HEIGHT, WIDTH},
+};
+use arduino_hal::{
+    hal::wdt::Wdt, pins, prelude::_unwrap_infallible_UnwrapInfallible, Adc, Eeprom, Peripherals,
+};
+use ttt::{
+    hexapawn::Hexapawn, position::pos, recording::Recording, runtime::Runtime, ttt::TicTacToe,
+};
+use ufmt::{uWrite, uwrite, uwriteln};
 
-mod buttons;
-mod display;
-mod hexapawn;
-mod millis;
-mod ttt;
-
-mod consts {
-    pub const SIZE: usize = 3;
+mod avr {
+    #[cfg(feature = "ldr")]
+    pub mod ambient;
+    pub mod buttons;
+    pub mod diagnostics;
+    pub mod display;
+    pub mod keypad;
+    pub mod millis;
+    pub mod panic;
+    pub mod sleep;
+    pub mod storage;
+    pub mod watchdog;
 }
-mod board {
-    use crate::consts::SIZE;
-    pub type Board<C> = [[C; SIZE]; SIZE];
-}
-
-mod player {
-    #[derive(Copy, Clone, PartialEq, Eq)]
-    pub enum Player {
-        Red,
-        Blue,
-    }
-
-    impl Player {
-        pub fn flip(&mut self) {
-            *self = match self {
-                Self::Red => Self::Blue,
-                Self::Blue => Self::Red,
-            }
-        }
-    }
 
-    macro_rules! choose {
-        ($peripherals:expr, $pins:expr) => {{
-            use arduino_hal::adc::AdcSettings;
-            use avr_hal_generic::{adc::Adc, clock::MHz16};
-            let mut adc: Adc<_, _, MHz16> = Adc::new($peripherals.ADC, AdcSettings::default());
-            if $pins.a5.into_analog_input(&mut adc).analog_read(&mut adc) & 1 == 0 {
-                Player::Red
-            } else {
-                Player::Blue
-            }
-        }};
-    }
+mod consts {
+    use ttt::player::Player;
 
-    pub(crate) use choose;
+    pub const WIDTH: usize = 3;
+    pub const HEIGHT: usize = 3;
+    /// The side the board plays itself, or `None` for two players.
+    pub const COMPUTER: Option<Player> = Some(Player::Blue);
 }
 
-mod game {
-    pub trait Game {
-        fn play() -> !;
-    }
+/// Gathers a seed from the noise in the lowest bit of the floating A5 pin.
+macro_rules! seed {
+    ($adc:expr, $pins:expr) => {{
+        let a5 = $pins.a5.into_analog_input(&mut $adc);
+        (0..16).fold(0u16, |seed, _| seed << 1 | (a5.analog_read(&mut $adc) & 1))
+    }};
 }
 
-mod position {
-    use crate::consts::SIZE;
-
-    #[derive(Clone, Copy, PartialEq, Eq)]
-    pub struct Position {
-        pub x: usize,
-        pub y: usize,
+#[arduino_hal::entry]
+fn main() -> ! {
+    let peripherals = Peripherals::take().unwrap();
+    init(&peripherals.TC0);
+    let pins = pins!(peripherals);
+    let mut adc = Adc::new(peripherals.ADC, Default::default());
+    let seed = seed!(adc, pins);
+    let watchdog = Wdt::new(peripherals.WDT, &peripherals.CPU.mcusr);
+    let mut display = display!(pins, peripherals.TC2);
+    let mut buttons = buttons!(pins, peripherals.EXINT, peripherals.CPU);
+    let (keypad, mut serial) = arduino_hal::default_serial!(peripherals, pins, 57600).split();
+    let mut storage = EepromStorage::new(Eeprom::new(peripherals.EEPROM));
+    if let Some(location) = Location::take(&mut storage) {
+        uwrite!(serial, "last boot ").unwrap_infallible();
+        location.log(&mut serial);
     }
-
-    pub const fn pos(x: usize, y: usize) -> Position {
-        Position { x, y }
+    if buttons.scan().exactly_one() == Some(pos(0, 0)) {
+        diagnostics::run(&mut display, &mut buttons, &mut serial);
     }
+    // Holding the bottom right key at power-up plays the last game back.
+    let mut replay = (buttons.scan().exactly_one() == Some(pos(WIDTH - 1, HEIGHT - 1)))
+        .then(|| Recording::load(&mut storage))
+        .flatten();
+    #[cfg(feature = "ldr")]
+    let display = AutoDim::new(display, adc);
+    let mut runtime = Runtime::new::<WIDTH, HEIGHT>(
+        display,
+        SerialKeypad::new(Watched::new(buttons, watchdog), keypad),
+        Timer0,
+        storage,
+        seed,
+    );
+    runtime.boot_options::<WIDTH, HEIGHT>();
 
-    impl Position {
-        pub fn offset_y(self, delta: isize) -> Option<Self> {
-            let y = self.y.checked_add_signed(delta)?;
-            if y < SIZE {
-                Some(pos(self.x, y))
-            } else {
-                None
+    loop {
+        match (1, replay.take()) {
+            (0, Some(recording)) => {
+                runtime.replay(&recording, |first| TicTacToe::<WIDTH>::new(first, COMPUTER))
             }
+            (0, None) => runtime.play(|first| TicTacToe::<WIDTH>::new(first, COMPUTER)),
+            (1, Some(recording)) => runtime.replay(&recording, |first| {
+                Hexapawn::<WIDTH, HEIGHT>::new(first, COMPUTER)
+            }),
+            (1, None) => runtime.play(|first| Hexapawn::<WIDTH, HEIGHT>::new(first, COMPUTER)),
+            _ => unreachable!(),
         }
-
-        pub fn offset_x(self, delta: isize) -> Option<Self> {
-            let x = self.x.checked_add_signed(delta)?;
-            if x < SIZE {
-                Some(pos(x, self.y))
-            } else {
-                None
-            }
+        // Passed to the simulator after the game's name, this plays the game
+        // back exactly, unless it filled up.
+        uwrite!(serial, "recording: ").unwrap_infallible();
+        for digit in runtime.recording().hex() {
+            serial.write_char(char::from(digit)).unwrap_infallible();
         }
-    }
-}
-
-#[arduino_hal::entry]
-fn main() -> ! {
-    unsafe extern "C" {
-        static mut DEVICE_PERIPHERALS: bool;
-    }
-    unsafe {
-        init(&Peripherals::steal().TC0);
-        DEVICE_PERIPHERALS = false;
-    }
-
-    match 1 {
-        0 => TicTacToe::play(),
-        1 => Hexapawn::play(),
-        _ => unreachable!(),
+        if runtime.recording().full() {
+            uwrite!(serial, " (full, so later input is missing)").unwrap_infallible();
+        }
+        uwriteln!(serial, "").unwrap_infallible();
     }
 }
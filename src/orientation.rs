@@ -0,0 +1,172 @@
+//! Which way up the board is, for boards mounted turned or mirrored and for
+//! playing from another side of the table.
+
+use core::array::from_fn;
+
+use crate::{
+    board::Board,
+    position::{pos, Position},
+    scan::ButtonScan,
+};
+
+/// How the board as games see it maps onto the hardware: mirrored left to
+/// right first, then turned clockwise.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// From 0 to 3. Only square boards can be turned a quarter, which
+    /// [`Self::from_byte`] and [`Self::next`] see to.
+    quarter_turns: u8,
+    mirrored: bool,
+}
+
+impl Orientation {
+    /// Orientations that can be stored, from [`Self::to_byte`].
+    const COUNT: u8 = 8;
+
+    const fn new(quarter_turns: u8, mirrored: bool) -> Self {
+        Self {
+            quarter_turns: quarter_turns % 4,
+            mirrored,
+        }
+    }
+
+    /// Reads back [`Self::to_byte`], if it is an orientation a `W`x`H` board
+    /// can take.
+    pub const fn from_byte<const W: usize, const H: usize>(byte: u8) -> Option<Self> {
+        let quarter_turns = byte & 3;
+        if byte < Self::COUNT && (W == H || quarter_turns.is_multiple_of(2)) {
+            Some(Self::new(quarter_turns, byte & 4 != 0))
+        } else {
+            None
+        }
+    }
+
+    pub const fn to_byte(self) -> u8 {
+        self.quarter_turns | if self.mirrored { 4 } else { 0 }
+    }
+
+    /// The next of the orientations a `W`x`H` board can take, for stepping
+    /// through them: all eight on a square board, and four on any other.
+    pub const fn next<const W: usize, const H: usize>(self) -> Self {
+        let mut byte = self.to_byte();
+        loop {
+            byte = (byte + 1) % Self::COUNT;
+            if let Some(next) = Self::from_byte::<W, H>(byte) {
+                return next;
+            }
+        }
+    }
+
+    /// Where `position`, as games see it, is on the hardware.
+    fn physical<const W: usize, const H: usize>(self, position: Position) -> Position {
+        let Position { mut x, y } = position;
+        if self.mirrored {
+            x = W - 1 - x;
+        }
+        // Odd turns only come up on square boards, where `W` and `H` agree.
+        match self.quarter_turns {
+            0 => pos(x, y),
+            1 => pos(W - 1 - y, x),
+            2 => pos(W - 1 - x, H - 1 - y),
+            _ => pos(y, W - 1 - x),
+        }
+    }
+
+    /// Moves each cell of `frame` to where it is on the hardware.
+    pub fn frame<T: Copy, const W: usize, const H: usize>(
+        self,
+        frame: Board<T, W, H>,
+    ) -> Board<T, W, H> {
+        let mut physical = frame;
+        for (y, row) in frame.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let position = self.physical::<W, H>(pos(x, y));
+                physical[position.y][position.x] = *cell;
+            }
+        }
+        physical
+    }
+
+    /// Reads `scan`, taken from the hardware, as games see the board.
+    pub fn scan<const W: usize, const H: usize>(self, scan: ButtonScan<W, H>) -> ButtonScan<W, H> {
+        ButtonScan::new(from_fn(|y| {
+            from_fn(|x| scan.pressed(self.physical::<W, H>(pos(x, y))))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::array::from_fn;
+
+    use super::Orientation;
+    use crate::{
+        position::{pos, Position},
+        scan::ButtonScan,
+    };
+
+    /// Checks that a cell drawn at each position lands on the key that reads
+    /// back as that position.
+    fn agrees<const W: usize, const H: usize>(orientation: Orientation) {
+        for y in 0..H {
+            for x in 0..W {
+                let frame =
+                    orientation.frame(from_fn(|row| from_fn(|col| pos(col, row) == pos(x, y))));
+                let keys = ButtonScan::<W, H>::new(frame);
+                assert_eq!(orientation.scan(keys).exactly_one(), Some(pos(x, y)));
+            }
+        }
+    }
+
+    /// Every orientation a `W`x`H` board can take, reached by stepping.
+    fn reachable<const W: usize, const H: usize>() -> Vec<Orientation> {
+        let mut orientations = vec![Orientation::default()];
+        loop {
+            let next = orientations.last().unwrap().next::<W, H>();
+            if next == Orientation::default() {
+                return orientations;
+            }
+            orientations.push(next);
+        }
+    }
+
+    #[test]
+    fn frame_and_scan_agree() {
+        for orientation in reachable::<3, 3>() {
+            agrees::<3, 3>(orientation);
+        }
+        for orientation in reachable::<4, 4>() {
+            agrees::<4, 4>(orientation);
+        }
+        for orientation in reachable::<5, 3>() {
+            agrees::<5, 3>(orientation);
+        }
+    }
+
+    #[test]
+    fn only_square_boards_turn_a_quarter() {
+        assert_eq!(reachable::<3, 3>().len(), 8);
+        assert_eq!(reachable::<5, 3>().len(), 4);
+        assert!(Orientation::from_byte::<5, 3>(1).is_none());
+        assert!(Orientation::from_byte::<5, 3>(6).is_some());
+    }
+
+    #[test]
+    fn turns_clockwise() {
+        let frame: [[Option<Position>; 3]; 3] =
+            Orientation::new(1, false).frame(from_fn(|y| from_fn(|x| Some(pos(x, y)))));
+        // The top left corner ends up top right.
+        assert_eq!(frame[0][2], Some(pos(0, 0)));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        for byte in 0..8 {
+            assert_eq!(
+                Orientation::from_byte::<3, 3>(byte).unwrap().to_byte(),
+                byte
+            );
+        }
+        assert!(Orientation::from_byte::<3, 3>(8).is_none());
+    }
+}
@@ -0,0 +1,20 @@
+/// A xorshift generator, small enough for the 328P and reproducible from its
+/// seed.
+#[derive(Clone, Copy)]
+pub struct Rng(u16);
+
+impl Rng {
+    /// A zero seed would only ever give zeros, so it is nudged.
+    pub const fn new(seed: u16) -> Self {
+        Self(if seed == 0 { 1 } else { seed })
+    }
+
+    pub fn next_u16(&mut self) -> u16 {
+        let mut x = self.0;
+        x ^= x << 7;
+        x ^= x >> 9;
+        x ^= x << 8;
+        self.0 = x;
+        x
+    }
+}
@@ -0,0 +1,285 @@
+//! Timestamped input and computer moves, kept so a session can be dumped and
+//! played back to reproduce exactly the same game.
+
+use crate::{
+    io::{Event, Storage},
+    position::{pos, Position},
+    time::{Duration, Instant},
+};
+
+/// Events kept per game, three bytes each. Later ones are dropped.
+const CAPACITY: usize = 64;
+
+/// The seed a game started from, and every event it was given.
+pub struct Recording {
+    seed: u16,
+    /// Milliseconds since the previous event, or since the game started, and
+    /// the event's code.
+    entries: [(u16, u8); CAPACITY],
+    len: usize,
+    /// When the last event was.
+    last: Instant,
+}
+
+impl Recording {
+    /// Where the last game's recording starts in [`Storage`], clear of the
+    /// settings and of anything the firmware keeps.
+    const OFFSET: u16 = 64;
+    /// Marks storage that has been written by [`Self::store`].
+    const MAGIC: u8 = 0x3e;
+    /// The magic byte, seed and length come before the entries.
+    const HEADER: usize = 4;
+
+    pub const fn new(seed: u16) -> Self {
+        Self {
+            seed,
+            entries: [(0, 0); CAPACITY],
+            len: 0,
+            last: Instant::ZERO,
+        }
+    }
+
+    pub const fn seed(&self) -> u16 {
+        self.seed
+    }
+
+    /// Whether events were dropped, so playing back won't match.
+    pub const fn full(&self) -> bool {
+        self.len == CAPACITY
+    }
+
+    /// Adds `event`, given at `now` on the game's clock.
+    /// Chords and resets are left out, as they act outside the game. Gaps
+    /// longer than a minute are shortened, which only the timing of
+    /// animations could tell.
+    pub fn record<const W: usize, const H: usize>(&mut self, now: Instant, event: Event) {
+        let Some(code) = encode::<W, H>(event) else {
+            return;
+        };
+        if self.full() {
+            return;
+        }
+        let delta = u16::try_from((now - self.last).as_millis()).unwrap_or(u16::MAX);
+        self.last = now;
+        self.entries[self.len] = (delta, code);
+        self.len += 1;
+    }
+
+    /// Each event and when it was given, on the game's clock.
+    pub fn events<const W: usize, const H: usize>(
+        &self,
+    ) -> impl Iterator<Item = (Instant, Event)> + '_ {
+        self.entries[..self.len]
+            .iter()
+            .scan(Instant::ZERO, |at, &(delta, code)| {
+                *at = *at + Duration::from_millis(delta.into());
+                Some(decode::<W, H>(code).map(|event| (*at, event)))
+            })
+            .flatten()
+    }
+
+    /// The recording as hex digits, for sending over serial: the seed, then
+    /// five digits per event.
+    pub fn hex(&self) -> impl Iterator<Item = u8> + '_ {
+        let [high, low] = self.seed.to_be_bytes();
+        [high, low]
+            .into_iter()
+            .chain(self.entries[..self.len].iter().flat_map(|&(delta, code)| {
+                let [high, low] = delta.to_be_bytes();
+                [high, low, code]
+            }))
+            .flat_map(|byte| [byte >> 4, byte & 0xf])
+            .map(|digit| b"0123456789abcdef"[usize::from(digit)])
+    }
+
+    /// Where in [`Storage`] entry `index` is kept.
+    fn offset(index: usize) -> u16 {
+        Self::OFFSET + u16::try_from(Self::HEADER + index * 3).unwrap()
+    }
+
+    /// The recording kept by [`Self::store`], if there is one.
+    pub fn load(storage: &mut impl Storage) -> Option<Self> {
+        let mut header = [0; Self::HEADER];
+        storage.read(Self::OFFSET, &mut header);
+        let [magic, high, low, len] = header;
+        let len = usize::from(len);
+        if magic != Self::MAGIC || len > CAPACITY {
+            return None;
+        }
+        let mut recording = Self::new(u16::from_be_bytes([high, low]));
+        for (index, entry) in recording.entries[..len].iter_mut().enumerate() {
+            let mut bytes = [0; 3];
+            storage.read(Self::offset(index), &mut bytes);
+            let [high, low, code] = bytes;
+            *entry = (u16::from_be_bytes([high, low]), code);
+        }
+        recording.len = len;
+        Some(recording)
+    }
+
+    /// Keeps the recording across power cycles, in place of the last one.
+    /// Only bytes that differ from what is stored are written, as each write
+    /// wears the EEPROM.
+    pub fn store(&self, storage: &mut impl Storage) {
+        let [high, low] = self.seed.to_be_bytes();
+        let len = u8::try_from(self.len).unwrap();
+        update(storage, Self::OFFSET, &[Self::MAGIC, high, low, len]);
+        for (index, &(delta, code)) in self.entries[..self.len].iter().enumerate() {
+            let [high, low] = delta.to_be_bytes();
+            update(storage, Self::offset(index), &[high, low, code]);
+        }
+    }
+
+    /// Reads back what [`Self::hex`] wrote.
+    pub fn from_hex(hex: &[u8]) -> Option<Self> {
+        if !hex.len().is_multiple_of(2) {
+            return None;
+        }
+        let mut bytes = hex.chunks(2).map(|pair| {
+            let text = core::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(text, 16).ok()
+        });
+        let seed = u16::from_be_bytes([bytes.next()??, bytes.next()??]);
+        let mut recording = Self::new(seed);
+        loop {
+            let Some(high) = bytes.next() else {
+                return Some(recording);
+            };
+            let delta = u16::from_be_bytes([high?, bytes.next()??]);
+            let code = bytes.next()??;
+            if recording.full() {
+                return None;
+            }
+            recording.entries[recording.len] = (delta, code);
+            recording.len += 1;
+        }
+    }
+}
+
+/// Writes `bytes` at `offset`, skipping those already stored.
+fn update(storage: &mut impl Storage, offset: u16, bytes: &[u8]) {
+    for (offset, &byte) in (offset..).zip(bytes) {
+        let mut stored = [0];
+        storage.read(offset, &mut stored);
+        if stored[0] != byte {
+            storage.write(offset, &[byte]);
+        }
+    }
+}
+
+/// Packs `event` into a byte: its kind in the high nibble, and in the low one
+/// the index of its key in reading order, or of the computer's move. A
+/// computer's move past the sixteenth can't be packed, which no game here
+/// offers.
+fn encode<const W: usize, const H: usize>(event: Event) -> Option<u8> {
+    const { assert!(W * H <= 16, "keys have to fit in a nibble") };
+    let key = |position: Position| position.y * W + position.x;
+    let (kind, index) = match event {
+        Event::Press(position) => (0, key(position)),
+        Event::Release(position) => (1, key(position)),
+        Event::Repeat(position) => (2, key(position)),
+        Event::LongPress(position) => (3, key(position)),
+        Event::DoubleTap(position) => (4, key(position)),
+        Event::Undo => (5, 0),
+        Event::Computer(index) => (6, usize::from(index)),
+        Event::Chord(_) | Event::Reset => return None,
+    };
+    let index = u8::try_from(index).ok().filter(|&index| index < 16)?;
+    Some(kind << 4 | index)
+}
+
+fn decode<const W: usize, const H: usize>(code: u8) -> Option<Event> {
+    let index = code & 0xf;
+    let position = pos(usize::from(index) % W, usize::from(index) / W);
+    Some(match code >> 4 {
+        5 => Event::Undo,
+        6 => Event::Computer(index),
+        _ if position.y >= H => return None,
+        0 => Event::Press(position),
+        1 => Event::Release(position),
+        2 => Event::Repeat(position),
+        3 => Event::LongPress(position),
+        4 => Event::DoubleTap(position),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Recording;
+    use crate::{
+        io::Event,
+        position::pos,
+        testing::Memory,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn hex_round_trip() {
+        let mut recording = Recording::new(0xbeef);
+        let events = [
+            (Instant::from_millis(120), Event::Press(pos(1, 2))),
+            (Instant::from_millis(250), Event::Release(pos(1, 2))),
+            (Instant::from_millis(251), Event::DoubleTap(pos(2, 0))),
+            (Instant::from_millis(70_000), Event::Undo),
+        ];
+        for (at, event) in events {
+            recording.record::<3, 3>(at, event);
+        }
+
+        let hex: Vec<u8> = recording.hex().collect();
+        let read = Recording::from_hex(&hex).unwrap();
+        assert_eq!(read.seed(), 0xbeef);
+        assert_eq!(read.hex().collect::<Vec<_>>(), hex);
+        let played: Vec<_> = read.events::<3, 3>().collect();
+        assert_eq!(played.len(), events.len());
+        for ((at, event), (played_at, played_event)) in events.iter().zip(&played).take(3) {
+            assert_eq!(at.as_millis(), played_at.as_millis());
+            assert_eq!(event, played_event);
+        }
+        // The minute-long gap is shortened.
+        let (at, event) = played[3];
+        assert_eq!(
+            at.as_millis(),
+            (Instant::from_millis(251) + Duration::from_millis(u16::MAX.into())).as_millis()
+        );
+        assert_eq!(event, Event::Undo);
+    }
+
+    #[test]
+    fn store_and_load() {
+        let mut memory = Memory::new();
+        assert!(Recording::load(&mut memory).is_none());
+
+        let mut recording = Recording::new(0x1234);
+        recording.record::<3, 3>(Instant::from_millis(300), Event::Press(pos(0, 2)));
+        recording.record::<3, 3>(Instant::from_millis(900), Event::Undo);
+        recording.store(&mut memory);
+        let loaded = Recording::load(&mut memory).unwrap();
+        assert_eq!(
+            loaded.hex().collect::<Vec<_>>(),
+            recording.hex().collect::<Vec<_>>()
+        );
+
+        // Storing one more event only writes it and the new length.
+        let written = memory.written;
+        recording.record::<3, 3>(Instant::from_millis(950), Event::Undo);
+        recording.store(&mut memory);
+        assert_eq!(memory.written - written, 4);
+    }
+
+    #[test]
+    fn leaves_out_chords_and_resets() {
+        let mut recording = Recording::new(0);
+        recording.record::<3, 3>(Instant::from_millis(10), Event::Reset);
+        assert_eq!(recording.events::<3, 3>().count(), 0);
+    }
+
+    #[test]
+    fn rejects_bad_hex() {
+        assert!(Recording::from_hex(b"12").is_none());
+        assert!(Recording::from_hex(b"00zz").is_none());
+        assert!(Recording::from_hex(b"0000001").is_none());
+        assert!(Recording::from_hex(b"123").is_none());
+    }
+}
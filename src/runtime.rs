@@ -0,0 +1,684 @@
+use core::iter::Peekable;
+
+use crate::{
+    animation::pulse_level,
+    board::Board,
+    display::DisplayPixel,
+    game::{Game, Tick},
+    gesture::{Chord, Gestures, Timing},
+    io::{Clock, Event, FrameSink, Input, InputSource, Storage},
+    orientation::Orientation,
+    player::Player,
+    position::pos,
+    random::Rng,
+    recording::Recording,
+    rules::Rules,
+    scan::ButtonScan,
+    scheduler::{self, Outbox, Task, TaskId, Wake},
+    search::Search,
+    settings::{Screen, Settings},
+    text::Scroll,
+    time::{Duration, Instant},
+    transition::{Playing, Transition},
+};
+
+/// How long without input before the display is blanked and the board sleeps.
+const IDLE_SLEEP: Duration = Duration::from_secs(60);
+/// The player whose colour pulses in colour-blind mode.
+const PULSING: Player = Player::Blue;
+/// How long each pulse takes in colour-blind mode, slow enough to tell apart
+/// from blinking.
+const PULSE_PERIOD: Duration = Duration::from_millis(1500);
+/// How often the keys are read.
+const POLL: Duration = Duration::from_millis(1);
+/// Moves the computer looks into each turn, few enough that the keys and
+/// display keep going while it thinks.
+const STEPS: usize = 16;
+/// How long the computer waits before moving, so its move can be followed.
+const THINK: Duration = Duration::from_millis(500);
+
+/// Owns the display, input, clock and storage, and drives whichever game is
+/// running.
+pub struct Runtime<D, I, C, S> {
+    display: D,
+    input: I,
+    clock: C,
+    storage: S,
+    settings: Settings,
+    timing: Timing,
+    rng: Rng,
+    /// The game being played, or the last one.
+    recording: Recording,
+}
+
+impl<D, I, C: Clock, S: Storage> Runtime<D, I, C, S> {
+    /// Games started with [`Self::play`] are drawn from `seed`, so it should
+    /// differ between power-ups.
+    pub fn new<const W: usize, const H: usize>(
+        display: D,
+        input: I,
+        clock: C,
+        mut storage: S,
+        seed: u16,
+    ) -> Self
+    where
+        D: FrameSink<W, H>,
+    {
+        let settings = Settings::load::<W, H>(&mut storage);
+        Self {
+            display,
+            input,
+            clock,
+            storage,
+            settings,
+            timing: Timing::default(),
+            rng: Rng::new(seed),
+            recording: Recording::new(seed),
+        }
+    }
+
+    /// The seed and input of the game being played, or the last one.
+    pub const fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+
+    /// Holding the bottom left key at power-up toggles colour-blind mode, and
+    /// scrolls whether it is now on across the matrix. Holding the top right
+    /// key steps to the next orientation, and scrolls "UP" the new way up.
+    /// Both keys are found the board's own way up, as the other keys held at
+    /// power-up are, so no orientation makes two of them the same key.
+    pub fn boot_options<const W: usize, const H: usize>(&mut self)
+    where
+        D: FrameSink<W, H>,
+        I: InputSource<W, H>,
+    {
+        let scan = self.input.read();
+        let text = match scan.exactly_one() {
+            Some(position) if position == pos(0, H - 1) => {
+                self.settings.colour_blind = !self.settings.colour_blind;
+                if self.settings.colour_blind {
+                    "CB ON"
+                } else {
+                    "CB OFF"
+                }
+            }
+            Some(position) if position == pos(W - 1, 0) => {
+                self.settings.orientation = self.settings.orientation.next::<W, H>();
+                "UP"
+            }
+            _ => return,
+        };
+        self.settings.store(&mut self.storage);
+        self.run(&mut Scroll::<W, H>::text(
+            text,
+            PULSING.into(),
+            Duration::from_millis(150),
+        ));
+    }
+
+    /// Starts the game `new_game` makes for the player who goes first, from a
+    /// fresh seed, and runs it while recording its input.
+    pub fn play<const W: usize, const H: usize, G: Game<W, H>>(
+        &mut self,
+        new_game: impl FnOnce(Player) -> G,
+    ) where
+        D: FrameSink<W, H>,
+        I: InputSource<W, H>,
+    {
+        let seed = self.rng.next_u16();
+        self.replay(&Recording::new(seed), new_game);
+    }
+
+    /// Plays `recording` back into the game `new_game` makes from its seed,
+    /// giving each event at the same time it was first given. The game
+    /// carries on with the keys once the recording runs out. Once it ends its
+    /// recording is stored, for playing back with [`Recording::load`].
+    ///
+    /// The computer's moves are played back from the recording too, at the
+    /// time they were first played, so they come out the same however long
+    /// finding them takes.
+    pub fn replay<const W: usize, const H: usize, G: Game<W, H>>(
+        &mut self,
+        recording: &Recording,
+        new_game: impl FnOnce(Player) -> G,
+    ) where
+        D: FrameSink<W, H>,
+        I: InputSource<W, H>,
+    {
+        self.recording = Recording::new(recording.seed());
+        let mut game = new_game(Player::from_seed(recording.seed()));
+        self.drive(&mut game, recording.events::<W, H>(), true);
+        self.recording.store(&mut self.storage);
+    }
+
+    /// Ticks `game` until it asks to exit. Holding the top left and bottom
+    /// right corners together opens the settings screen, after which `game`
+    /// carries on. Holding all four corners, or [`Event::Reset`], ends `game`
+    /// early. After a while without input the board sleeps, and `game`
+    /// resumes once it wakes.
+    ///
+    /// Input, the game, the computer and the display each run as a task of
+    /// their own, passing each other messages.
+    pub fn run<const W: usize, const H: usize>(&mut self, game: &mut impl Game<W, H>)
+    where
+        D: FrameSink<W, H>,
+        I: InputSource<W, H>,
+    {
+        self.drive(game, [].into_iter(), false);
+    }
+
+    /// Runs `game` as [`Self::run`] does, taking events from `script` until
+    /// it runs out and then from the keys, and recording them if `record` is
+    /// set.
+    fn drive<const W: usize, const H: usize, G: Game<W, H>>(
+        &mut self,
+        game: &mut G,
+        script: impl Iterator<Item = (Instant, Event)>,
+        record: bool,
+    ) where
+        D: FrameSink<W, H>,
+        I: InputSource<W, H>,
+    {
+        let settings = self.settings;
+        self.display.set_brightness(settings.brightness);
+        let mut input = InputTask {
+            input: &mut self.input,
+            keys: Gestures::new(self.timing),
+            orientation: settings.orientation,
+            script: script.peekable(),
+            started: self.clock.now(),
+            active_at: None,
+            sleeping: false,
+        };
+        let mut game = GameTask {
+            game,
+            screen: None,
+            settings: &mut self.settings,
+            storage: &mut self.storage,
+            recording: record.then_some(&mut self.recording),
+            asked: None,
+            asked_at: Instant::ZERO,
+            found: None,
+        };
+        let mut computer = ComputerTask::<G::Rules> { search: None };
+        let mut display = DisplayTask {
+            display: &mut self.display,
+            settings,
+            shown: [[DisplayPixel::default(); W]; H],
+            transition: None,
+        };
+        scheduler::run(
+            &self.clock,
+            [&mut input, &mut game, &mut computer, &mut display],
+        );
+    }
+}
+
+/// What the tasks running a game tell each other.
+#[derive(Clone, Copy)]
+enum Message<const W: usize, const H: usize, R: Rules> {
+    /// From input to the game: time for a tick, on the game's clock.
+    Tick {
+        input: Input,
+        now: Instant,
+        /// Whether events are still being played back, the computer's moves
+        /// among them.
+        replaying: bool,
+    },
+    /// From the game to the computer: the position to find a move from, or
+    /// `None` to stop looking.
+    Think(Option<R>),
+    /// From the computer to the game: the move it found.
+    Moved(R::Move),
+    /// From the game to the display.
+    Frame {
+        frame: Board<DisplayPixel, W, H>,
+        transition: Option<Transition>,
+        now: Instant,
+        /// Whether the frame has to be shown before the game goes on.
+        synced: bool,
+    },
+    /// From the settings screen to the display.
+    Brightness(u8),
+    /// From input to the display, before the board sleeps.
+    Blank,
+}
+
+/// Where the tasks are in the array given to [`scheduler::run`], after input.
+/// There is no sound task, as the board has no buzzer to drive.
+const GAME: TaskId = 1;
+const COMPUTER: TaskId = 2;
+const DISPLAY: TaskId = 3;
+
+/// Reads the keys, and paces the game by sending it a tick each turn with
+/// whatever happened since the last one.
+struct InputTask<'a, I, E: Iterator, const W: usize, const H: usize> {
+    input: &'a mut I,
+    keys: Gestures<W, H>,
+    orientation: Orientation,
+    /// Events played back instead of the keys until it runs out.
+    script: Peekable<E>,
+    /// When the game started, on the runtime's clock.
+    started: Instant,
+    /// The last event, on the game's clock. Taken to be the next turn after
+    /// waking, as `now` is from before the board slept.
+    active_at: Option<Instant>,
+    /// Whether the display has been asked to blank, so the board can sleep.
+    sleeping: bool,
+}
+
+impl<I, E, const W: usize, const H: usize> InputTask<'_, I, E, W, H>
+where
+    I: InputSource<W, H>,
+    E: Iterator,
+{
+    /// Which keys are pressed, as games see the board.
+    fn scan(&mut self) -> ButtonScan<W, H> {
+        self.orientation.scan(self.input.read())
+    }
+}
+
+impl<I, E, R, const W: usize, const H: usize> Task<Message<W, H, R>> for InputTask<'_, I, E, W, H>
+where
+    I: InputSource<W, H>,
+    E: Iterator<Item = (Instant, Event)>,
+    R: Rules + Copy,
+{
+    fn run(
+        &mut self,
+        now: Instant,
+        _message: Option<Message<W, H, R>>,
+        outbox: &mut Outbox<Message<W, H, R>>,
+    ) -> Wake {
+        let next = now + POLL;
+        let mut now = Instant::ZERO + (now - self.started);
+        if self.sleeping {
+            // The display is blank by now. The key that wakes the board is
+            // ignored until let go, so it doesn't play a move.
+            self.input.sleep();
+            while self.scan().positions().next().is_some() {}
+            self.sleeping = false;
+            self.active_at = None;
+            return Wake::Soon;
+        }
+        let scan = self.scan();
+        self.keys.update(scan, now);
+        let live = self.keys.pop().or_else(|| self.input.event());
+        let event = match self.script.peek() {
+            // Chords and resets still work during playback, so it can be
+            // ended early.
+            _ if matches!(live, Some(Event::Chord(_) | Event::Reset)) => live,
+            None => live,
+            // Ticking at the recorded time keeps the game's timing decisions
+            // the same as when it was recorded.
+            Some(&(at, event)) if now.reached(at) => {
+                self.script.next();
+                now = at;
+                Some(event)
+            }
+            Some(_) => None,
+        };
+        let active_at = *self.active_at.get_or_insert(now);
+        if event.is_some() {
+            self.active_at = Some(now);
+        } else if now - active_at >= IDLE_SLEEP {
+            self.sleeping = true;
+            outbox.send(DISPLAY, Message::Blank);
+            return Wake::Soon;
+        }
+        outbox.send(
+            GAME,
+            Message::Tick {
+                input: Input { event },
+                now,
+                replaying: self.script.peek().is_some(),
+            },
+        );
+        Wake::At(next)
+    }
+}
+
+/// Ticks the game, or the settings screen while it is open, and plays the
+/// computer's moves.
+struct GameTask<'a, G, S, R: Rules> {
+    game: &'a mut G,
+    screen: Option<Screen>,
+    settings: &'a mut Settings,
+    storage: &'a mut S,
+    recording: Option<&'a mut Recording>,
+    /// The position the computer was last asked to find a move from.
+    asked: Option<R>,
+    /// When the computer was asked, on the game's clock.
+    asked_at: Instant,
+    /// The move found from `asked`, played once it has waited [`THINK`].
+    found: Option<R::Move>,
+}
+
+impl<G, S, R: Rules + Copy> GameTask<'_, G, S, R> {
+    /// Plays `mv` from the position the computer was asked about, recording
+    /// it by its place among the legal moves.
+    fn computer_move<const W: usize, const H: usize>(&mut self, mv: R::Move, now: Instant)
+    where
+        G: Game<W, H, Rules = R>,
+    {
+        let index = self.asked.and_then(|position| {
+            position
+                .legal_moves()
+                .into_iter()
+                .position(|legal| legal == mv)
+        });
+        if let (Some(recording), Some(index)) = (self.recording.as_mut(), index) {
+            if let Ok(index) = u8::try_from(index) {
+                recording.record::<W, H>(now, Event::Computer(index));
+            }
+        }
+        self.found = None;
+        self.game.computer_move(mv);
+    }
+}
+
+impl<G, S, const W: usize, const H: usize> Task<Message<W, H, G::Rules>>
+    for GameTask<'_, G, S, G::Rules>
+where
+    G: Game<W, H>,
+    S: Storage,
+{
+    fn run(
+        &mut self,
+        _now: Instant,
+        message: Option<Message<W, H, G::Rules>>,
+        outbox: &mut Outbox<Message<W, H, G::Rules>>,
+    ) -> Wake {
+        let (input, now, replaying) = match message {
+            Some(Message::Tick {
+                input,
+                now,
+                replaying,
+            }) => (input, now, replaying),
+            Some(Message::Moved(mv)) => {
+                // Only if it is still for the game as it is.
+                if self.asked.is_some() && self.game.think() == self.asked {
+                    self.found = Some(mv);
+                }
+                return Wake::OnMessage;
+            }
+            _ => return Wake::OnMessage,
+        };
+        if let Some(screen) = &mut self.screen {
+            let tick = Game::<W, H>::tick(screen, &input, now);
+            outbox.send(DISPLAY, Message::Brightness(screen.settings().brightness));
+            match tick {
+                Tick::Frame(frame) | Tick::Transition(frame, _) => outbox.send(
+                    DISPLAY,
+                    Message::Frame {
+                        frame,
+                        transition: None,
+                        now,
+                        synced: false,
+                    },
+                ),
+                Tick::Exit => {
+                    if screen.settings() != *self.settings {
+                        *self.settings = screen.settings();
+                        self.settings.store(self.storage);
+                    }
+                    self.screen = None;
+                }
+            }
+            return Wake::OnMessage;
+        }
+        match input.event {
+            Some(Event::Chord(Chord::Diagonal)) => {
+                self.screen = Some(Screen::new(*self.settings));
+                return Wake::OnMessage;
+            }
+            Some(Event::Chord(Chord::FourCorners) | Event::Reset) => return Wake::Stop,
+            _ => {}
+        }
+        // While playing back, the computer's moves come from the recording
+        // instead, whenever they were first played.
+        if !replaying && now - self.asked_at >= THINK {
+            if let Some(mv) = self.found {
+                self.computer_move(mv, now);
+            }
+        }
+        let mut input = input;
+        match input.event {
+            Some(Event::Computer(index)) => {
+                input.event = None;
+                let mv = self.asked.and_then(|position| {
+                    position.legal_moves().into_iter().nth(usize::from(index))
+                });
+                if let Some(mv) = mv.filter(|_| self.game.think() == self.asked) {
+                    self.computer_move(mv, now);
+                }
+            }
+            Some(event) => {
+                if let Some(recording) = self.recording.as_mut().filter(|_| self.game.wants(event))
+                {
+                    recording.record::<W, H>(now, event);
+                }
+            }
+            None => {}
+        }
+        let (frame, transition) = match self.game.tick(&input, now) {
+            Tick::Frame(frame) => (frame, None),
+            Tick::Transition(frame, transition) => (frame, Some(transition)),
+            Tick::Exit => return Wake::Stop,
+        };
+        outbox.send(
+            DISPLAY,
+            Message::Frame {
+                frame,
+                transition,
+                now,
+                synced: self.game.synced(),
+            },
+        );
+        let position = self.game.think();
+        if position != self.asked {
+            outbox.send(COMPUTER, Message::Think(position));
+            self.asked = position;
+            self.asked_at = now;
+            self.found = None;
+        }
+        Wake::OnMessage
+    }
+}
+
+/// Finds the computer's moves a few positions at a time.
+struct ComputerTask<R: Rules> {
+    search: Option<Search<R>>,
+}
+
+impl<R: Rules + Copy, const W: usize, const H: usize> Task<Message<W, H, R>> for ComputerTask<R> {
+    fn run(
+        &mut self,
+        _now: Instant,
+        message: Option<Message<W, H, R>>,
+        outbox: &mut Outbox<Message<W, H, R>>,
+    ) -> Wake {
+        if let Some(Message::Think(position)) = message {
+            self.search = position.map(|position| Search::new(position, R::LOOKAHEAD));
+        }
+        let Some(search) = &mut self.search else {
+            return Wake::OnMessage;
+        };
+        for _ in 0..STEPS {
+            if search.step() {
+                if let Some(mv) = search.best() {
+                    outbox.send(GAME, Message::Moved(mv));
+                }
+                self.search = None;
+                return Wake::OnMessage;
+            }
+        }
+        Wake::Soon
+    }
+}
+
+/// Shows the frames the game sends, easing in transitions.
+struct DisplayTask<'a, D, const W: usize, const H: usize> {
+    display: &'a mut D,
+    settings: Settings,
+    /// The last frame shown, for transitions to start from.
+    shown: Board<DisplayPixel, W, H>,
+    transition: Option<Playing<W, H>>,
+}
+
+impl<D: FrameSink<W, H>, const W: usize, const H: usize> DisplayTask<'_, D, W, H> {
+    /// Writes `frame` to the display the right way up, pulsing [`PULSING`]'s
+    /// colour in colour-blind mode.
+    fn present(&mut self, frame: Board<DisplayPixel, W, H>, now: Instant) {
+        let frame = if self.settings.colour_blind {
+            let level = pulse_level((now - Instant::ZERO) % PULSE_PERIOD, PULSE_PERIOD);
+            frame.map(|row| row.map(|pixel| pixel.scale_player(PULSING, level)))
+        } else {
+            frame
+        };
+        self.display.write(self.settings.orientation.frame(frame));
+        self.display.show();
+    }
+}
+
+impl<D, R, const W: usize, const H: usize> Task<Message<W, H, R>> for DisplayTask<'_, D, W, H>
+where
+    D: FrameSink<W, H>,
+    R: Rules + Copy,
+{
+    fn run(
+        &mut self,
+        _now: Instant,
+        message: Option<Message<W, H, R>>,
+        _outbox: &mut Outbox<Message<W, H, R>>,
+    ) -> Wake {
+        match message {
+            Some(Message::Frame {
+                frame,
+                transition,
+                now,
+                synced,
+            }) => {
+                if let Some(style) = transition {
+                    self.transition = Some(Playing::new(style, self.shown, now));
+                }
+                if self
+                    .transition
+                    .as_ref()
+                    .is_some_and(|playing| playing.finished(now))
+                {
+                    self.transition = None;
+                }
+                self.shown = match &self.transition {
+                    Some(playing) => playing.draw(frame, now),
+                    None => frame,
+                };
+                self.present(self.shown, now);
+                if synced {
+                    self.display.wait_for_swap();
+                }
+            }
+            Some(Message::Brightness(level)) => self.display.set_brightness(level),
+            Some(Message::Blank) => {
+                self.display.write([[DisplayPixel::default(); W]; H]);
+                self.display.show();
+                self.display.wait_for_swap();
+            }
+            Some(Message::Tick { .. } | Message::Think(_) | Message::Moved(_)) | None => {}
+        }
+        Wake::OnMessage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Runtime;
+    use crate::{
+        io::Event,
+        orientation::Orientation,
+        player::Player,
+        position::pos,
+        settings::Settings,
+        testing::{Matrix, Memory, Millis, Serial},
+        ttt::TicTacToe,
+    };
+
+    /// Runs `play` against the computer with the clock moving on `step`
+    /// milliseconds at every reading, until a reset typed well after the last
+    /// move.
+    fn run(
+        step: u32,
+        play: impl FnOnce(&mut Runtime<Matrix, Serial, Millis, Memory>),
+        typed: &[(u32, Event)],
+    ) -> Runtime<Matrix, Serial, Millis, Memory> {
+        let clock = Millis::new(step);
+        let mut typed = typed.to_vec();
+        typed.push((12_000, Event::Reset));
+        let serial = Serial {
+            clock: clock.clone(),
+            held: Vec::new(),
+            typed: typed.into(),
+        };
+        let mut runtime = Runtime::new(Matrix::default(), serial, clock, Memory::new(), 7);
+        play(&mut runtime);
+        runtime
+    }
+
+    #[test]
+    fn replay_plays_the_computer_the_same() {
+        let new_game = |first| TicTacToe::<3>::new(first, Some(Player::Blue));
+        let live = run(
+            1,
+            |runtime| runtime.play(new_game),
+            &[
+                (3_000, Event::Press(pos(1, 1))),
+                (6_000, Event::Press(pos(0, 2))),
+            ],
+        );
+        let recording = live.recording();
+        assert!(recording
+            .events::<3, 3>()
+            .any(|(_, event)| matches!(event, Event::Computer(_))));
+
+        // Slow enough that the computer finds its moves after they were due.
+        let replayed = run(5, |runtime| runtime.replay(recording, new_game), &[]);
+        assert!(replayed.display.0 == live.display.0);
+        assert!(replayed.recording().hex().eq(recording.hex()));
+    }
+
+    #[test]
+    fn boot_options_keep_clear_of_the_other_boot_keys() {
+        // Colour-blind mode, the next orientation, diagnostics and playing
+        // the last game back, as held on the board.
+        let keys = [
+            (pos(0, 2), true, true),
+            (pos(2, 0), false, false),
+            (pos(0, 0), false, true),
+            (pos(2, 2), false, true),
+        ];
+        for orientation in (0..=u8::MAX).filter_map(Orientation::from_byte::<3, 3>) {
+            for (key, colour_blind, same_way_up) in keys {
+                let mut memory = Memory::new();
+                Settings {
+                    orientation,
+                    ..Settings::default()
+                }
+                .store(&mut memory);
+                let clock = Millis::new(1);
+                let serial = Serial {
+                    clock: clock.clone(),
+                    held: vec![key],
+                    typed: [].into(),
+                };
+                let mut runtime = Runtime::new(Matrix::default(), serial, clock, memory, 0);
+                runtime.boot_options::<3, 3>();
+                assert_eq!(runtime.settings.colour_blind, colour_blind);
+                assert_eq!(runtime.settings.orientation == orientation, same_way_up);
+            }
+        }
+    }
+}
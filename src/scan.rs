@@ -0,0 +1,59 @@
+use core::array::from_fn;
+
+use itertools::Itertools;
+
+use crate::{
+    board::Board,
+    position::{pos, Position},
+};
+
+/// Which keys of the matrix read as pressed at one moment.
+///
+/// The matrix has no diodes, so pressing three corners of a rectangle of keys
+/// makes the fourth read as pressed too. A reading with all four corners of a
+/// rectangle pressed can't say which of them, if any, is a phantom.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ButtonScan<const W: usize, const H: usize>(Board<bool, W, H>);
+
+impl<const W: usize, const H: usize> ButtonScan<W, H> {
+    pub const fn new(pressed: Board<bool, W, H>) -> Self {
+        Self(pressed)
+    }
+
+    pub const fn pressed(&self, position: Position) -> bool {
+        self.0[position.y][position.x]
+    }
+
+    /// Every key read as pressed, in reading order.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..H)
+            .flat_map(|y| (0..W).map(move |x| pos(x, y)))
+            .filter(|position| self.pressed(*position))
+    }
+
+    pub fn exactly_one(&self) -> Option<Position> {
+        self.positions().exactly_one().ok()
+    }
+
+    /// Keys at the corners of rectangles whose corners all read as pressed,
+    /// any of which might be a phantom.
+    pub fn ghosts(&self) -> Board<bool, W, H> {
+        let mut ghosts = [[false; W]; H];
+        for (top, bottom) in (0..H).tuple_combinations() {
+            let shared: [bool; W] = from_fn(|x| self.0[top][x] && self.0[bottom][x]);
+            if shared.iter().filter(|shared| **shared).count() >= 2 {
+                for (x, shared) in shared.into_iter().enumerate() {
+                    ghosts[top][x] |= shared;
+                    ghosts[bottom][x] |= shared;
+                }
+            }
+        }
+        ghosts
+    }
+
+    /// Whether every key read as pressed really is, as far as the matrix can
+    /// tell.
+    pub fn trustworthy(&self) -> bool {
+        self.ghosts().iter().flatten().all(|ghost| !ghost)
+    }
+}
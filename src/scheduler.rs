@@ -0,0 +1,186 @@
+//! A cooperative scheduler. Tasks take turns, each doing a short piece of
+//! work and saying when it next wants a turn, and talk to each other through
+//! small mailboxes rather than sharing state.
+//!
+//! Nothing is allocated: a set of tasks costs their own state plus
+//! [`INBOX`] messages each, so messages should be kept small. A task that
+//! takes long over a turn holds up every other one, so long work, such as the
+//! computer's search, is split over several turns. While no task has anything
+//! to do, the scheduler waits on the clock, which on the board idles the CPU
+//! until the next interrupt.
+
+use core::array::from_fn;
+
+use crate::{io::Clock, queue::Queue, time::Instant};
+
+/// Messages waiting per task. Every task takes all of its messages each
+/// round, so this only has to hold what it is sent in one: the runtime sends
+/// none of its tasks more than two. Any more are dropped, which debug builds
+/// treat as a bug.
+const INBOX: usize = 2;
+/// Messages a task can send in one turn. Any more are dropped, which debug
+/// builds treat as a bug.
+const OUTBOX: usize = 2;
+
+/// A task's index in the array given to [`run`].
+pub type TaskId = usize;
+
+/// When a task wants its next turn. A message for it always gives it one.
+#[derive(Clone, Copy)]
+pub enum Wake {
+    /// Once every other task has had a turn.
+    Soon,
+    /// Once `Instant` is reached.
+    At(Instant),
+    /// Only once a message arrives.
+    OnMessage,
+    /// Stops every task, returning from [`run`].
+    Stop,
+}
+
+/// Messages sent during a turn, delivered once it ends.
+pub struct Outbox<M> {
+    sent: Queue<(TaskId, M), OUTBOX>,
+}
+
+impl<M: Copy> Outbox<M> {
+    pub fn send(&mut self, to: TaskId, message: M) {
+        debug_assert!(!self.sent.is_full(), "too many messages sent in one turn");
+        self.sent.push((to, message));
+    }
+}
+
+pub trait Task<M> {
+    /// Takes a turn, handling `message` if there is one. A task with several
+    /// messages waiting gets a turn for each.
+    fn run(&mut self, now: Instant, message: Option<M>, outbox: &mut Outbox<M>) -> Wake;
+}
+
+/// Gives `tasks` turns, in order, until one of them asks to stop. Every task
+/// starts with a turn of its own. Rounds in which no task gets a turn end by
+/// waiting on `clock`.
+pub fn run<M: Copy, const N: usize>(clock: &impl Clock, mut tasks: [&mut dyn Task<M>; N]) {
+    let mut wakes = [Wake::Soon; N];
+    let mut inboxes: [Queue<M, INBOX>; N] = from_fn(|_| Queue::new());
+    loop {
+        let mut idle = true;
+        for (id, task) in tasks.iter_mut().enumerate() {
+            let now = clock.now();
+            let mut message = inboxes[id].pop();
+            let due = match wakes[id] {
+                Wake::Soon => true,
+                Wake::At(at) => now.reached(at),
+                Wake::OnMessage | Wake::Stop => false,
+            };
+            if !due && message.is_none() {
+                continue;
+            }
+            idle = false;
+            loop {
+                let mut outbox = Outbox { sent: Queue::new() };
+                wakes[id] = task.run(now, message, &mut outbox);
+                if let Wake::Stop = wakes[id] {
+                    return;
+                }
+                while let Some((to, sent)) = outbox.sent.pop() {
+                    debug_assert!(!inboxes[to].is_full(), "task {to} was sent too much");
+                    inboxes[to].push(sent);
+                }
+                message = inboxes[id].pop();
+                if message.is_none() {
+                    break;
+                }
+            }
+        }
+        if idle {
+            clock.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Outbox, Task, Wake};
+    use crate::{
+        io::Clock,
+        testing::Millis,
+        time::{Duration, Instant},
+    };
+
+    /// A task that takes its turns with `turn`, noting when each was and the
+    /// message it came with.
+    struct Logged<F> {
+        turn: F,
+        turns: Vec<(u32, Option<u8>)>,
+    }
+
+    impl<F> Logged<F> {
+        fn new(turn: F) -> Self {
+            Self {
+                turn,
+                turns: Vec::new(),
+            }
+        }
+    }
+
+    impl<F: FnMut(Instant, Option<u8>, &mut Outbox<u8>) -> Wake> Task<u8> for Logged<F> {
+        fn run(&mut self, now: Instant, message: Option<u8>, outbox: &mut Outbox<u8>) -> Wake {
+            self.turns.push((now.as_millis(), message));
+            (self.turn)(now, message, outbox)
+        }
+    }
+
+    #[test]
+    fn delivers_messages_in_order_once_the_turn_ends() {
+        let mut receiver = Logged::new(|_, message, _: &mut Outbox<u8>| match message {
+            Some(2) => Wake::Stop,
+            _ => Wake::OnMessage,
+        });
+        let mut sender = Logged::new(|_, _, outbox: &mut Outbox<u8>| {
+            outbox.send(0, 1);
+            outbox.send(0, 2);
+            Wake::OnMessage
+        });
+        run(&Millis::new(1), [&mut receiver, &mut sender]);
+        let messages: Vec<_> = receiver.turns.iter().map(|&(_, message)| message).collect();
+        assert_eq!(messages, [None, Some(1), Some(2)]);
+        assert_eq!(sender.turns.len(), 1);
+    }
+
+    #[test]
+    fn wakes_each_task_when_it_asked() {
+        let clock = Millis::new(1);
+        let mut timed =
+            Logged::new(|now, _, _: &mut Outbox<u8>| Wake::At(now + Duration::from_millis(10)));
+        let mut soon = Logged::new(|_, _, _: &mut Outbox<u8>| Wake::Soon);
+        let mut idle = Logged::new(|_, _, _: &mut Outbox<u8>| Wake::OnMessage);
+        let mut stop = Logged::new(|now: Instant, _, _: &mut Outbox<u8>| {
+            if now.reached(Instant::from_millis(100)) {
+                Wake::Stop
+            } else {
+                Wake::Soon
+            }
+        });
+        run(&clock, [&mut timed, &mut soon, &mut idle, &mut stop]);
+        for pair in timed.turns.windows(2) {
+            assert!((10..14).contains(&(pair[1].0 - pair[0].0)));
+        }
+        assert!(soon.turns.len() >= 20);
+        assert_eq!(idle.turns.len(), 1);
+        assert!(clock.now().reached(Instant::from_millis(100)));
+    }
+
+    #[test]
+    #[should_panic(expected = "task 0 was sent too much")]
+    fn catches_a_full_inbox() {
+        let mut receiver = Logged::new(|_, _, _: &mut Outbox<u8>| Wake::OnMessage);
+        let send_two = |_, _, outbox: &mut Outbox<u8>| {
+            outbox.send(0, 1);
+            outbox.send(0, 2);
+            Wake::OnMessage
+        };
+        let mut first = Logged::new(send_two);
+        let mut second = Logged::new(send_two);
+        run(&Millis::new(1), [&mut receiver, &mut first, &mut second]);
+    }
+}
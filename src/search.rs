@@ -0,0 +1,150 @@
+//! Depth-limited negamax with alpha-beta pruning, for any [`Rules`].
+//!
+//! A [`Search`] goes one move at a time, so it can be spread over many turns
+//! of a task without holding up the others. Nothing is allocated, and each ply
+//! keeps only how far through its moves it has got, which suits the 2 KiB of
+//! RAM on the board.
+
+use core::array::from_fn;
+
+use crate::rules::{Rules, Status};
+
+/// Plies a search can look ahead.
+pub const MAX_DEPTH: u8 = 8;
+/// Score of a won position, before adding the depth left so that quicker wins
+/// are preferred.
+const WIN: i16 = 1000;
+
+/// Where a search is in one position.
+#[derive(Clone, Copy)]
+struct Ply<M> {
+    /// Moves looked into so far, in the order of [`Rules::legal_moves`].
+    tried: usize,
+    /// The move being looked into, applied to the position.
+    current: Option<M>,
+    alpha: i16,
+    beta: i16,
+    best: i16,
+}
+
+impl<M> Ply<M> {
+    const fn new(alpha: i16, beta: i16) -> Self {
+        Self {
+            tried: 0,
+            current: None,
+            alpha,
+            beta,
+            best: -i16::MAX,
+        }
+    }
+}
+
+/// A search for the best move for the side to move, in progress.
+pub struct Search<R: Rules> {
+    rules: R,
+    depth: u8,
+    plies: [Ply<R::Move>; MAX_DEPTH as usize],
+    /// Plies in use, the first being the position searched from.
+    len: usize,
+    best: Option<R::Move>,
+}
+
+impl<R: Rules> Search<R> {
+    /// Starts looking `depth` plies ahead of `rules`, up to [`MAX_DEPTH`].
+    pub fn new(rules: R, depth: u8) -> Self {
+        let ongoing = rules.status() == Status::Ongoing;
+        Self {
+            rules,
+            depth: depth.clamp(1, MAX_DEPTH),
+            plies: from_fn(|_| Ply::new(-i16::MAX, i16::MAX)),
+            len: usize::from(ongoing),
+            best: None,
+        }
+    }
+
+    /// Looks into one more move, returning whether the search is over.
+    pub fn step(&mut self) -> bool {
+        let Some(top) = self.len.checked_sub(1) else {
+            return true;
+        };
+        let ply = self.plies[top];
+        let next = if ply.alpha < ply.beta {
+            self.rules.legal_moves().into_iter().nth(ply.tried)
+        } else {
+            None
+        };
+        let Some(mv) = next else {
+            // Every move from here has been looked into, or the rest can't
+            // make a difference.
+            self.len = top;
+            if top > 0 {
+                self.back_up(-ply.best);
+            }
+            return self.len == 0;
+        };
+
+        self.plies[top].tried += 1;
+        self.plies[top].current = Some(mv);
+        self.rules.apply(mv);
+        // `top` is below `depth`, which fits in a `u8`.
+        let remaining = self.depth - (top as u8 + 1);
+        match self.settled(remaining) {
+            Some(score) => self.back_up(-score),
+            None => {
+                self.plies[top + 1] = Ply::new(-ply.beta, -ply.alpha);
+                self.len += 1;
+            }
+        }
+        false
+    }
+
+    /// The best move found so far, which is the best there is once
+    /// [`Self::step`] says the search is over. `None` if the game is over.
+    pub const fn best(&self) -> Option<R::Move> {
+        self.best
+    }
+
+    /// The score of the position for the side to move, if it needs no more
+    /// searching: the game is over, or there are no `remaining` plies left.
+    fn settled(&self, remaining: u8) -> Option<i16> {
+        match self.rules.status() {
+            Status::Won(winner) => {
+                let score = WIN + i16::from(remaining);
+                Some(if winner == self.rules.side_to_move() {
+                    score
+                } else {
+                    -score
+                })
+            }
+            Status::Draw => Some(0),
+            Status::Ongoing if remaining == 0 => Some(self.rules.evaluate()),
+            Status::Ongoing => None,
+        }
+    }
+
+    /// Takes back the move being looked into from the last ply, which scored
+    /// `score` for the side that made it.
+    fn back_up(&mut self, score: i16) {
+        let top = self.len - 1;
+        let ply = &mut self.plies[top];
+        let Some(mv) = ply.current.take() else {
+            return;
+        };
+        self.rules.undo(mv);
+        if score > ply.best {
+            ply.best = score;
+            if top == 0 {
+                self.best = Some(mv);
+            }
+        }
+        ply.alpha = ply.alpha.max(score);
+    }
+}
+
+/// The best move for the side to move, looking `depth` plies ahead, found all
+/// at once.
+pub fn best_move<R: Rules>(rules: R, depth: u8) -> Option<R::Move> {
+    let mut search = Search::new(rules, depth);
+    while !search.step() {}
+    search.best()
+}
@@ -0,0 +1,157 @@
+use core::array::from_fn;
+
+use crate::{
+    display::DisplayPixel,
+    game::{Game, Tick},
+    io::{Event, Input, Storage},
+    orientation::Orientation,
+    rules::NoRules,
+    time::{Duration, Instant, Timer},
+};
+
+/// How long the settings screen stays open without a press.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Preferences kept across power cycles.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    /// Scales every pixel, from 1 to [`DisplayPixel::MAX`].
+    pub brightness: u8,
+    /// Pulses one player's colour, for those who can't tell the LEDs apart.
+    pub colour_blind: bool,
+    /// Applied to every frame and key, so the board can be mounted or
+    /// played from any side.
+    pub orientation: Orientation,
+}
+
+impl Settings {
+    /// Where the settings start in [`Storage`].
+    const OFFSET: u16 = 0;
+    /// Marks storage that has been written by [`Self::store`], since erased
+    /// EEPROM reads as `0xff`.
+    const MAGIC: u8 = 0x7a;
+
+    /// Falls back to the defaults for anything missing or out of range, such
+    /// as an orientation a `W`x`H` board can't take.
+    pub fn load<const W: usize, const H: usize>(storage: &mut impl Storage) -> Self {
+        let mut bytes = [0; 4];
+        storage.read(Self::OFFSET, &mut bytes);
+        let [magic, brightness, colour_blind, orientation] = bytes;
+        let mut settings = Self::default();
+        if magic != Self::MAGIC {
+            return settings;
+        }
+        if (1..=DisplayPixel::MAX).contains(&brightness) {
+            settings.brightness = brightness;
+        }
+        if matches!(colour_blind, 0 | 1) {
+            settings.colour_blind = colour_blind == 1;
+        }
+        if let Some(orientation) = Orientation::from_byte::<W, H>(orientation) {
+            settings.orientation = orientation;
+        }
+        settings
+    }
+
+    pub fn store(self, storage: &mut impl Storage) {
+        storage.write(
+            Self::OFFSET,
+            &[
+                Self::MAGIC,
+                self.brightness,
+                self.colour_blind.into(),
+                self.orientation.to_byte(),
+            ],
+        );
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            brightness: DisplayPixel::MAX,
+            colour_blind: false,
+            orientation: Orientation::default(),
+        }
+    }
+}
+
+/// Shows the brightness as a bar filling the matrix in reading order, and sets
+/// it to match whichever cell is pressed. Exits once left alone.
+pub struct Screen {
+    settings: Settings,
+    /// Time since the last press.
+    idle: Timer,
+}
+
+impl Screen {
+    pub const fn new(settings: Settings) -> Self {
+        Self {
+            settings,
+            idle: Timer::new(),
+        }
+    }
+
+    pub const fn settings(&self) -> Settings {
+        self.settings
+    }
+}
+
+impl<const W: usize, const H: usize> Game<W, H> for Screen {
+    type Rules = NoRules;
+
+    fn tick(&mut self, input: &Input, now: Instant) -> Tick<W, H> {
+        let cells = W * H;
+        match input.event {
+            Some(Event::Press(position)) => {
+                let level = (position.y * W + position.x + 1) * usize::from(DisplayPixel::MAX);
+                self.settings.brightness = u8::try_from(level / cells)
+                    .unwrap_or(DisplayPixel::MAX)
+                    .max(1);
+                self.idle.restart();
+            }
+            Some(_) => self.idle.restart(),
+            None => {}
+        }
+        if self.idle.after(now, IDLE_TIMEOUT) {
+            return Tick::Exit;
+        }
+
+        let lit = (usize::from(self.settings.brightness) * cells)
+            .div_ceil(usize::from(DisplayPixel::MAX));
+        Tick::Frame(from_fn(|y| {
+            from_fn(|x| DisplayPixel::red().and(y * W + x < lit))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Settings;
+    use crate::{orientation::Orientation, testing::Memory};
+
+    #[test]
+    fn round_trip() {
+        let mut memory = Memory::new();
+        assert!(Settings::load::<3, 3>(&mut memory) == Settings::default());
+        let settings = Settings {
+            brightness: 3,
+            colour_blind: true,
+            orientation: Orientation::default().next::<3, 3>(),
+        };
+        settings.store(&mut memory);
+        assert!(Settings::load::<3, 3>(&mut memory) == settings);
+    }
+
+    #[test]
+    fn quarter_turn_on_a_board_that_is_not_square() {
+        let mut memory = Memory::new();
+        Settings {
+            orientation: Orientation::default().next::<3, 3>(),
+            ..Settings::default()
+        }
+        .store(&mut memory);
+        let loaded = Settings::load::<5, 3>(&mut memory);
+        assert!(loaded.orientation == Orientation::default());
+    }
+}
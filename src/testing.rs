@@ -0,0 +1,173 @@
+//! Stand-ins for the board's hardware, and checks of the computer's play,
+//! shared by the tests.
+
+use core::array::from_fn;
+use std::{cell::Cell, collections::VecDeque, rc::Rc};
+
+use crate::{
+    board::Board,
+    debounce::Debouncer,
+    display::DisplayPixel,
+    gesture::Gestures,
+    io::{Clock, Event, FrameSink, InputSource, Storage},
+    player::Player,
+    position::{pos, Position},
+    rules::{Rules, Status},
+    scan::ButtonScan,
+    search::best_move,
+    time::Instant,
+};
+
+/// A reading of the keys with exactly `keys` pressed.
+pub fn scan(keys: &[Position]) -> ButtonScan<3, 3> {
+    ButtonScan::new(from_fn(|y| from_fn(|x| keys.contains(&pos(x, y)))))
+}
+
+/// Turns readings of the keys into events, as [`Debouncer`] and [`Gestures`]
+/// do.
+pub trait Keys {
+    fn update(&mut self, scan: ButtonScan<3, 3>, now: Instant);
+
+    fn pop(&mut self) -> Option<Event>;
+}
+
+impl Keys for Debouncer<3, 3> {
+    fn update(&mut self, scan: ButtonScan<3, 3>, now: Instant) {
+        Debouncer::update(self, scan, now);
+    }
+
+    fn pop(&mut self) -> Option<Event> {
+        Debouncer::pop(self)
+    }
+}
+
+impl Keys for Gestures<3, 3> {
+    fn update(&mut self, scan: ButtonScan<3, 3>, now: Instant) {
+        Gestures::update(self, scan, now);
+    }
+
+    fn pop(&mut self) -> Option<Event> {
+        Gestures::pop(self)
+    }
+}
+
+/// Reads `held` every millisecond from `from` until just before `to`, and
+/// returns the events queued.
+pub fn hold(keys: &mut impl Keys, held: &[Position], from: u32, to: u32) -> Vec<Event> {
+    for millis in from..to {
+        keys.update(scan(held), Instant::from_millis(millis));
+    }
+    core::iter::from_fn(|| keys.pop()).collect()
+}
+
+/// As much EEPROM as the ATmega328P has, starting out erased.
+pub struct Memory {
+    bytes: [u8; 1024],
+    /// Bytes written so far, as each write wears the EEPROM.
+    pub written: usize,
+}
+
+impl Memory {
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0xff; 1024],
+            written: 0,
+        }
+    }
+}
+
+impl Storage for Memory {
+    fn read(&mut self, offset: u16, buffer: &mut [u8]) {
+        let offset = usize::from(offset);
+        buffer.copy_from_slice(&self.bytes[offset..offset + buffer.len()]);
+    }
+
+    fn write(&mut self, offset: u16, bytes: &[u8]) {
+        let offset = usize::from(offset);
+        self.bytes[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self.written += bytes.len();
+    }
+}
+
+/// A clock that moves on `step` milliseconds every time it is read, standing
+/// in for the time each turn of the runtime takes. Clones share the time.
+#[derive(Clone)]
+pub struct Millis {
+    now: Rc<Cell<u32>>,
+    step: u32,
+}
+
+impl Millis {
+    pub fn new(step: u32) -> Self {
+        Self {
+            now: Rc::new(Cell::new(0)),
+            step,
+        }
+    }
+}
+
+impl Clock for Millis {
+    fn now(&self) -> Instant {
+        self.now.set(self.now.get() + self.step);
+        Instant::from_millis(self.now.get())
+    }
+}
+
+/// Keys held down throughout, and events typed over serial, each once
+/// `clock` reaches it.
+pub struct Serial {
+    pub clock: Millis,
+    pub held: Vec<Position>,
+    pub typed: VecDeque<(u32, Event)>,
+}
+
+impl InputSource<3, 3> for Serial {
+    fn read(&mut self) -> ButtonScan<3, 3> {
+        scan(&self.held)
+    }
+
+    fn event(&mut self) -> Option<Event> {
+        let &(at, _) = self.typed.front()?;
+        if at > self.clock.now.get() {
+            return None;
+        }
+        self.typed.pop_front().map(|(_, event)| event)
+    }
+}
+
+/// The LED matrix, keeping the last frame written.
+#[derive(Default)]
+pub struct Matrix(pub Board<DisplayPixel, 3, 3>);
+
+impl FrameSink<3, 3> for Matrix {
+    fn write(&mut self, buffer: Board<DisplayPixel, 3, 3>) {
+        self.0 = buffer;
+    }
+
+    fn set_brightness(&mut self, _level: u8) {}
+}
+
+/// How many ways the side not played by `computer` can beat it from
+/// `position`.
+pub fn losses<R: Rules + Copy>(position: &mut R, computer: Player) -> u32 {
+    match position.status() {
+        Status::Won(winner) => return u32::from(winner != computer),
+        Status::Draw => return 0,
+        Status::Ongoing => {}
+    }
+    if position.side_to_move() == computer {
+        let mv = best_move(*position, R::LOOKAHEAD).unwrap();
+        position.apply(mv);
+        let losses = losses(position, computer);
+        position.undo(mv);
+        losses
+    } else {
+        let mut total = 0;
+        for mv in position.legal_moves() {
+            position.apply(mv);
+            total += losses(position, computer);
+            position.undo(mv);
+        }
+        total
+    }
+}
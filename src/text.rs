@@ -0,0 +1,175 @@
+//! A 3x3 font of digits and a reduced alphabet, and text scrolled across the
+//! matrix with it.
+
+use core::array::from_fn;
+
+use crate::{
+    board::Board,
+    display::DisplayPixel,
+    game::{Game, Tick},
+    io::Input,
+    rules::NoRules,
+    time::{Duration, Instant},
+};
+
+const GLYPH_HEIGHT: usize = 3;
+/// Blank columns between glyphs.
+const SPACING: usize = 1;
+/// Bytes of text a [`Scroll`] can hold.
+const CAPACITY: usize = 16;
+
+#[derive(Clone, Copy)]
+struct Glyph {
+    /// One bit per row, top first, for each column.
+    columns: [u8; 3],
+    width: usize,
+}
+
+/// Builds a glyph from its rows, top first, with `#` for lit cells.
+const fn glyph(rows: [&str; GLYPH_HEIGHT]) -> Glyph {
+    let width = rows[0].len();
+    let mut columns = [0; 3];
+    let mut y = 0;
+    while y < GLYPH_HEIGHT {
+        let mut x = 0;
+        while x < width {
+            if rows[y].as_bytes()[x] == b'#' {
+                columns[x] |= 1 << y;
+            }
+            x += 1;
+        }
+        y += 1;
+    }
+    Glyph { columns, width }
+}
+
+/// Letters are case-insensitive, and anything without a glyph is a space.
+const fn lookup(character: u8) -> Glyph {
+    match character.to_ascii_uppercase() {
+        b'0' | b'O' => const { glyph(["###", "# #", "###"]) },
+        b'1' => const { glyph(["## ", " # ", "###"]) },
+        b'2' => const { glyph(["## ", " # ", " ##"]) },
+        b'3' => const { glyph(["###", " ##", "###"]) },
+        b'4' => const { glyph(["# #", "###", "  #"]) },
+        b'5' | b'S' => const { glyph([" ##", " # ", "## "]) },
+        b'6' => const { glyph(["#  ", "###", "###"]) },
+        b'7' => const { glyph(["###", "  #", "  #"]) },
+        b'8' => const { glyph(["###", "###", "###"]) },
+        b'9' => const { glyph(["###", "###", "  #"]) },
+        b'A' => const { glyph([" # ", "###", "# #"]) },
+        b'C' => const { glyph(["###", "#  ", "###"]) },
+        b'E' => const { glyph(["###", "## ", "###"]) },
+        b'F' => const { glyph(["###", "## ", "#  "]) },
+        b'H' => const { glyph(["# #", "###", "# #"]) },
+        b'I' => const { glyph(["#", "#", "#"]) },
+        b'L' => const { glyph(["#  ", "#  ", "###"]) },
+        b'N' => const { glyph(["###", "# #", "# #"]) },
+        b'P' => const { glyph(["###", "###", "#  "]) },
+        b'R' => const { glyph(["## ", "## ", "# #"]) },
+        b'T' => const { glyph(["###", " # ", " # "]) },
+        b'U' => const { glyph(["# #", "# #", "###"]) },
+        b'Y' => const { glyph(["# #", " # ", " # "]) },
+        b'-' => const { glyph(["   ", "###", "   "]) },
+        _ => const { glyph(["  ", "  ", "  "]) },
+    }
+}
+
+/// Text that enters from the right, scrolls across the matrix once and leaves
+/// on the left. Its clock starts the first time it is drawn.
+#[derive(Clone, Copy)]
+pub struct Scroll<const W: usize, const H: usize> {
+    text: [u8; CAPACITY],
+    len: usize,
+    colour: DisplayPixel,
+    /// How long the text stays put before moving one column.
+    speed: Duration,
+    started: Option<Instant>,
+}
+
+impl<const W: usize, const H: usize> Scroll<W, H> {
+    const SUPPORTED: () = assert!(H >= GLYPH_HEIGHT, "text needs at least three rows");
+
+    /// Only the first [`CAPACITY`] bytes are kept.
+    pub fn text(text: &str, colour: DisplayPixel, speed: Duration) -> Self {
+        let () = Self::SUPPORTED;
+        assert!(speed > Duration::ZERO, "text has to move");
+        let len = text.len().min(CAPACITY);
+        let mut buffer = [b' '; CAPACITY];
+        buffer[..len].copy_from_slice(&text.as_bytes()[..len]);
+        Self {
+            text: buffer,
+            len,
+            colour,
+            speed,
+            started: None,
+        }
+    }
+
+    fn glyphs(&self) -> impl Iterator<Item = Glyph> + '_ {
+        self.text[..self.len]
+            .iter()
+            .map(|&character| lookup(character))
+    }
+
+    /// Columns in the whole text, including the spacing after each glyph.
+    fn width(&self) -> usize {
+        self.glyphs().map(|glyph| glyph.width + SPACING).sum()
+    }
+
+    /// The lit rows of column `index` of the text.
+    fn column(&self, mut index: usize) -> u8 {
+        for glyph in self.glyphs() {
+            if index < glyph.width {
+                return glyph.columns[index];
+            }
+            if index < glyph.width + SPACING {
+                return 0;
+            }
+            index -= glyph.width + SPACING;
+        }
+        0
+    }
+
+    /// Columns scrolled so far, starting from the text being just off the
+    /// right edge.
+    fn scrolled(&self, now: Instant) -> usize {
+        self.started.map_or(0, |started| {
+            usize::try_from((now - started) / self.speed).unwrap_or(usize::MAX)
+        })
+    }
+
+    pub fn draw(&mut self, now: Instant) -> Board<DisplayPixel, W, H> {
+        self.started.get_or_insert(now);
+        let scrolled = self.scrolled(now);
+        let top = (H - GLYPH_HEIGHT) / 2;
+        let columns: [u8; W] = from_fn(|x| {
+            scrolled
+                .saturating_add(x)
+                .checked_sub(W)
+                .map_or(0, |index| self.column(index))
+        });
+        from_fn(|y| {
+            from_fn(|x| {
+                let lit = y >= top && y < top + GLYPH_HEIGHT && columns[x] >> (y - top) & 1 == 1;
+                self.colour.and(lit)
+            })
+        })
+    }
+
+    /// Whether the text has left the matrix.
+    pub fn finished(&self, now: Instant) -> bool {
+        self.started.is_some() && self.scrolled(now) >= self.width() + W
+    }
+}
+
+/// Scrolls the text once, then exits.
+impl<const W: usize, const H: usize> Game<W, H> for Scroll<W, H> {
+    type Rules = NoRules;
+
+    fn tick(&mut self, _input: &Input, now: Instant) -> Tick<W, H> {
+        if self.finished(now) {
+            return Tick::Exit;
+        }
+        Tick::Frame(self.draw(now))
+    }
+}
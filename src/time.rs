@@ -0,0 +1,173 @@
+//! Points in time and the spans between them, counted in milliseconds by a
+//! `u32` that wraps about every 49 days. Comparing instants directly breaks
+//! at the wrap, so they only compare through the spans between them.
+
+use core::ops::{Add, AddAssign, Div, Mul, Rem, Sub};
+
+/// A point in time, such as a reading of [`crate::io::Clock`].
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Instant(u32);
+
+impl Instant {
+    /// Where a game's clock starts.
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_millis(millis: u32) -> Self {
+        Self(millis)
+    }
+
+    pub const fn as_millis(self) -> u32 {
+        self.0
+    }
+
+    /// The time from `earlier` to `self`, correct across the wrap as long as
+    /// less than 49 days passed.
+    pub const fn duration_since(self, earlier: Self) -> Duration {
+        Duration(self.0.wrapping_sub(earlier.0))
+    }
+
+    /// Whether `self` is at or past `deadline`, for deadlines up to 24 days
+    /// either side.
+    pub const fn reached(self, deadline: Self) -> bool {
+        self.0.wrapping_sub(deadline.0) < 1 << 31
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Self;
+
+    fn add(self, duration: Duration) -> Self {
+        Self(self.0.wrapping_add(duration.0))
+    }
+}
+
+impl Sub for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Self) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+/// A span of time, up to 49 days.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration(u32);
+
+impl Duration {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_millis(millis: u32) -> Self {
+        Self(millis)
+    }
+
+    pub const fn from_secs(secs: u32) -> Self {
+        Self(secs * 1000)
+    }
+
+    pub const fn as_millis(self) -> u32 {
+        self.0
+    }
+
+    pub const fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Mul<u32> for Duration {
+    type Output = Self;
+
+    fn mul(self, factor: u32) -> Self {
+        Self(self.0.saturating_mul(factor))
+    }
+}
+
+impl Div<u32> for Duration {
+    type Output = Self;
+
+    fn div(self, divisor: u32) -> Self {
+        Self(self.0 / divisor)
+    }
+}
+
+/// How many times `divisor` fits in `self`.
+impl Div for Duration {
+    type Output = u32;
+
+    fn div(self, divisor: Self) -> u32 {
+        self.0 / divisor.0
+    }
+}
+
+/// What is left of `self` after as many `divisor`s as fit.
+impl Rem for Duration {
+    type Output = Self;
+
+    fn rem(self, divisor: Self) -> Self {
+        Self(self.0 % divisor.0)
+    }
+}
+
+/// Times how long something has been going on, such as a game state, starting
+/// the first time it is asked so it can be restarted without knowing the time.
+#[derive(Clone, Copy, Default)]
+pub struct Timer {
+    started: Option<Instant>,
+}
+
+impl Timer {
+    pub const fn new() -> Self {
+        Self { started: None }
+    }
+
+    /// Starts the timer over from the next time it is asked.
+    pub fn restart(&mut self) {
+        self.started = None;
+    }
+
+    pub fn elapsed(&mut self, now: Instant) -> Duration {
+        now - *self.started.get_or_insert(now)
+    }
+
+    /// Whether `duration` has passed since the timer started.
+    pub fn after(&mut self, now: Instant, duration: Duration) -> bool {
+        self.elapsed(now) >= duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Duration, Instant};
+
+    #[test]
+    fn reached_across_the_wrap() {
+        let deadline = Instant::from_millis(u32::MAX - 5) + Duration::from_millis(10);
+        assert_eq!(deadline.as_millis(), 4);
+        assert!(!Instant::from_millis(u32::MAX - 1).reached(deadline));
+        assert!(!Instant::from_millis(3).reached(deadline));
+        assert!(Instant::from_millis(4).reached(deadline));
+        assert!(Instant::from_millis(1000).reached(deadline));
+    }
+
+    #[test]
+    fn duration_since_across_the_wrap() {
+        let earlier = Instant::from_millis(u32::MAX - 1);
+        assert_eq!(
+            (Instant::from_millis(3) - earlier).as_millis(),
+            Duration::from_millis(5).as_millis()
+        );
+    }
+}
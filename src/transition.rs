@@ -0,0 +1,121 @@
+use core::array::from_fn;
+
+use crate::{
+    board::Board,
+    display::DisplayPixel,
+    time::{Duration, Instant},
+};
+
+/// How long a transition takes.
+pub const DURATION: Duration = Duration::from_millis(500);
+
+/// How one frame gives way to the next.
+#[derive(Clone, Copy)]
+pub enum Transition {
+    /// The new frame replaces the old one column by column, from the left.
+    Wipe,
+    /// Cells switch to the new frame one by one, in a scrambled order.
+    Dissolve,
+    /// Cells switch to the new frame clockwise from the top left corner,
+    /// working inwards.
+    Spiral,
+    /// The old frame dims to nothing and the new one brightens from it.
+    Fade,
+}
+
+impl Transition {
+    /// Where cell (`x`, `y`) comes in the order cells switch, out of `W * H`.
+    fn rank<const W: usize, const H: usize>(self, x: usize, y: usize) -> usize {
+        match self {
+            Self::Wipe => x * H + y,
+            Self::Dissolve => {
+                // Knuth's multiplicative hash spreads neighbouring indices
+                // apart; ties just switch together.
+                let hash = u32::try_from(y * W + x)
+                    .unwrap()
+                    .wrapping_mul(2_654_435_761)
+                    >> 16;
+                usize::try_from((hash * u32::try_from(W * H).unwrap()) >> 16).unwrap()
+            }
+            Self::Spiral => {
+                let ring = x.min(y).min(W - 1 - x).min(H - 1 - y);
+                let outer: usize = (0..ring)
+                    .map(|ring| perimeter(W - 2 * ring, H - 2 * ring))
+                    .sum();
+                let (width, height) = (W - 2 * ring, H - 2 * ring);
+                let (dx, dy) = (x - ring, y - ring);
+                outer
+                    + if dy == 0 {
+                        dx
+                    } else if dx == width - 1 {
+                        width - 1 + dy
+                    } else if dy == height - 1 {
+                        2 * (width - 1) + dy - dx
+                    } else {
+                        2 * (width - 1) + 2 * (height - 1) - dy
+                    }
+            }
+            Self::Fade => 0,
+        }
+    }
+}
+
+/// Cells on the edge of a `width`x`height` rectangle.
+const fn perimeter(width: usize, height: usize) -> usize {
+    if width == 1 || height == 1 {
+        width * height
+    } else {
+        2 * (width + height) - 4
+    }
+}
+
+/// A transition being played from `from` to whatever is drawn next.
+pub struct Playing<const W: usize, const H: usize> {
+    transition: Transition,
+    from: Board<DisplayPixel, W, H>,
+    started: Instant,
+}
+
+impl<const W: usize, const H: usize> Playing<W, H> {
+    pub const fn new(
+        transition: Transition,
+        from: Board<DisplayPixel, W, H>,
+        now: Instant,
+    ) -> Self {
+        Self {
+            transition,
+            from,
+            started: now,
+        }
+    }
+
+    /// Blends the old frame with `to`, which can keep changing while the
+    /// transition plays.
+    pub fn draw(&self, to: Board<DisplayPixel, W, H>, now: Instant) -> Board<DisplayPixel, W, H> {
+        let elapsed = (now - self.started).min(DURATION);
+        if let Transition::Fade = self.transition {
+            let half = DURATION / 2;
+            let (frame, distance) = if elapsed < half {
+                (self.from, half.saturating_sub(elapsed))
+            } else {
+                (to, elapsed.saturating_sub(half))
+            };
+            let level = u8::try_from(distance * u32::from(DisplayPixel::MAX) / half).unwrap();
+            return frame.map(|row| row.map(|pixel| pixel.scale(level)));
+        }
+        let switched = usize::try_from(elapsed * u32::try_from(W * H).unwrap() / DURATION).unwrap();
+        from_fn(|y| {
+            from_fn(|x| {
+                if self.transition.rank::<W, H>(x, y) < switched {
+                    to[y][x]
+                } else {
+                    self.from[y][x]
+                }
+            })
+        })
+    }
+
+    pub fn finished(&self, now: Instant) -> bool {
+        now - self.started >= DURATION
+    }
+}
@@ -1,106 +1,138 @@
-use core::{array::from_fn, convert::Into};
+use core::{
+    array::{from_fn, IntoIter},
+    convert::Into,
+    iter::Flatten,
+};
 
-use arduino_hal::{pins, Peripherals};
 use itertools::Itertools;
-use millis::millis;
 
 use crate::{
+    animation::{self, Animation, Effect, Playback},
     board::Board,
-    buttons::{buttons, Buttons},
-    consts::SIZE,
-    display::{display, Display, DisplayPixel},
-    game::Game,
-    millis,
-    player::{choose, Player},
-    position::Position,
+    display::DisplayPixel,
+    game::{Game, Tick},
+    io::{Event, Input},
+    player::Player,
+    position::{pos, Position},
+    rules::{Rules, Status},
+    time::{Duration, Instant},
+    transition::Transition,
+    turn::Turns,
 };
 
-pub struct TicTacToe {
-    board: Board<Cell>,
-    current_player: Player,
-    display: Display,
-    state: GameState,
-    buttons: Buttons,
+pub struct TicTacToe<const N: usize> {
+    turns: Turns<Grid<N>, GameState, N, N>,
 }
 
-impl TicTacToe {
-    pub fn new() -> Self {
-        let peripherals = Peripherals::take().unwrap();
-        let pins = pins!(peripherals);
-        let current_player = choose!(peripherals, pins);
+impl<const N: usize> TicTacToe<N> {
+    /// A game `current_player` starts, with `computer` playing one side or
+    /// `None` for two players.
+    pub fn new(current_player: Player, computer: Option<Player>) -> Self {
         Self {
-            board: [[None; SIZE]; SIZE],
-            current_player,
-            display: display!(pins, [[DisplayPixel::from(current_player); SIZE]; SIZE]),
-            state: GameState::default(),
-            buttons: buttons!(pins),
+            turns: Turns::new(Grid::new(current_player), computer),
         }
     }
+}
 
-    pub fn run(mut self) -> ! {
-        loop {
-            match self.state {
-                GameState::PresentCurrentPlayer => {
-                    if millis() > 1000 {
-                        self.state = GameState::WaitForMove;
-                    }
-                }
-                GameState::WaitForMove => {
-                    if let Some(Position { x, y }) = self.buttons.scan().exactly_one() {
-                        if self.board[y][x].is_none() {
-                            self.board[y][x] = Some(self.current_player);
-                            self.current_player.flip();
-                        };
-                    }
-                    self.display
-                        .write(self.board.map(|row| row.map(Into::into)));
-                    if let Some(winner) = self.winner() {
-                        self.state = GameState::DisplayWinner(winner);
-                    }
-                    if self.board.as_flattened().iter().all(Option::is_some) {
-                        self.state = GameState::DisplayTie;
+/// Any square board, with `N` in a row needed to win.
+impl<const N: usize> Game<N, N> for TicTacToe<N> {
+    type Rules = Grid<N>;
+
+    fn tick(&mut self, input: &Input, now: Instant) -> Tick<N, N> {
+        let turns = &mut self.turns;
+        if let Some(Event::Undo) = input.event {
+            turns.undo(GameState::WaitForMove, Transition::Fade);
+        }
+        let frame = match turns.state {
+            GameState::PresentCurrentPlayer => {
+                turns.present(now, GameState::WaitForMove, Transition::Fade)
+            }
+            GameState::WaitForMove => {
+                if let Some(Event::Press(position)) =
+                    input.event.filter(|_| !turns.computers_turn())
+                {
+                    if turns.grid.cells[position.y][position.x].is_none() {
+                        turns.play(position, GameState::WaitForMove);
                     }
                 }
-                GameState::DisplayWinner(winner) => {
-                    let mut pixels = from_fn::<_, { SIZE * SIZE }, _>(|index| {
-                        DisplayPixel::from(winner)
-                            .and(index % 2 == usize::from(millis() % 1000 < 500))
-                    })
-                    .into_iter();
-                    self.display
-                        .write(from_fn(|_| from_fn(|_| pixels.next().unwrap())));
-                }
-                GameState::DisplayTie => {
-                    let mut pixels = from_fn::<_, { SIZE * SIZE }, _>(|index| {
-                        DisplayPixel::red_if(index % 2 == usize::from(millis() % 1000 < 500))
-                    })
-                    .into_iter();
-                    self.display
-                        .write(from_fn(|_| from_fn(|_| pixels.next().unwrap())));
+                let result = match turns.grid.status() {
+                    Status::Ongoing => None,
+                    Status::Won(winner) => Some((winner.into(), DisplayPixel::default())),
+                    Status::Draw => Some((DisplayPixel::red(), DisplayPixel::blue())),
+                };
+                if let Some((on, off)) = result {
+                    turns.enter(GameState::DisplayResult);
+                    turns.transition = Some(Transition::Dissolve);
+                    turns.animation = Some(Animation::new(
+                        Effect::Checkerboard { on, off },
+                        Duration::from_secs(1),
+                        Playback::Loop,
+                    ));
                 }
+                turns.grid.cells.map(|row| row.map(Into::into))
             }
-            self.display.show();
+            GameState::DisplayResult => [[DisplayPixel::default(); N]; N],
+        };
+        turns.tick(frame, now)
+    }
+
+    fn wants(&self, event: Event) -> bool {
+        matches!(event, Event::Press(_) | Event::Undo)
+    }
+
+    fn think(&self) -> Option<Grid<N>> {
+        self.turns
+            .think(matches!(self.turns.state, GameState::WaitForMove))
+    }
+
+    fn computer_move(&mut self, position: Position) {
+        self.turns
+            .play_computer(position, GameState::WaitForMove, animation::cell(position));
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+enum GameState {
+    #[default]
+    PresentCurrentPlayer,
+    WaitForMove,
+    /// The winner's colour, or both for a tie, shown until reset.
+    DisplayResult,
+}
+
+/// Who has played where, and whose turn it is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Grid<const N: usize> {
+    cells: Board<Cell, N, N>,
+    current_player: Player,
+}
+
+impl<const N: usize> Grid<N> {
+    pub const fn new(current_player: Player) -> Self {
+        Self {
+            cells: [[None; N]; N],
+            current_player,
         }
     }
 
     fn winner(&self) -> Option<Player> {
-        for row in &self.board {
+        for row in &self.cells {
             if let Ok(&Some(player)) = row.iter().all_equal_value() {
                 return Some(player);
             }
         }
-        for x in 0..SIZE {
-            if let Ok(Some(player)) = self.board.iter().map(|row| row[x]).all_equal_value() {
+        for x in 0..N {
+            if let Ok(Some(player)) = self.cells.iter().map(|row| row[x]).all_equal_value() {
                 return Some(player);
             }
         }
-        if let Ok(Some(player)) = from_fn::<_, SIZE, _>(|i| self.board[i][i])
+        if let Ok(Some(player)) = from_fn::<_, N, _>(|i| self.cells[i][i])
             .into_iter()
             .all_equal_value()
         {
             return Some(player);
         }
-        if let Ok(Some(player)) = from_fn::<_, SIZE, _>(|i| self.board[i][2 - i])
+        if let Ok(Some(player)) = from_fn::<_, N, _>(|i| self.cells[i][N - 1 - i])
             .into_iter()
             .all_equal_value()
         {
@@ -110,25 +142,43 @@ impl TicTacToe {
     }
 }
 
-impl Game for TicTacToe {
-    fn play() -> ! {
-        Self::new().run();
+impl<const N: usize> Rules for Grid<N> {
+    type Move = Position;
+    type Moves = Flatten<Flatten<IntoIter<[Option<Position>; N], N>>>;
+
+    /// Enough never to lose on 3x3.
+    const LOOKAHEAD: u8 = 6;
+
+    fn side_to_move(&self) -> Player {
+        self.current_player
     }
-}
 
-impl Default for TicTacToe {
-    fn default() -> Self {
-        Self::new()
+    fn legal_moves(&self) -> Self::Moves {
+        from_fn(|y| from_fn(|x| self.cells[y][x].is_none().then_some(pos(x, y))))
+            .into_iter()
+            .flatten()
+            .flatten()
     }
-}
 
-#[derive(Default, Clone, Copy)]
-enum GameState {
-    #[default]
-    PresentCurrentPlayer,
-    WaitForMove,
-    DisplayWinner(Player),
-    DisplayTie,
+    fn apply(&mut self, position: Position) {
+        self.cells[position.y][position.x] = Some(self.current_player);
+        self.current_player.flip();
+    }
+
+    fn undo(&mut self, position: Position) {
+        self.cells[position.y][position.x] = None;
+        self.current_player.flip();
+    }
+
+    fn status(&self) -> Status {
+        if let Some(winner) = self.winner() {
+            Status::Won(winner)
+        } else if self.cells.as_flattened().iter().all(Option::is_some) {
+            Status::Draw
+        } else {
+            Status::Ongoing
+        }
+    }
 }
 
 type Cell = Option<Player>;
@@ -138,3 +188,77 @@ impl From<Cell> for DisplayPixel {
         cell.map(Into::into).unwrap_or_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+    use crate::{
+        player::Player,
+        position::{pos, Position},
+        rules::{Rules, Status},
+        search::best_move,
+        testing::losses,
+    };
+
+    /// A game with `moves` played in turn, red first.
+    fn played(moves: &[Position]) -> Grid<3> {
+        let mut grid = Grid::new(Player::Red);
+        for &position in moves {
+            grid.apply(position);
+        }
+        grid
+    }
+
+    #[test]
+    fn apply_and_undo_round_trip() {
+        let start = played(&[pos(1, 1), pos(0, 0)]);
+        let mut grid = start;
+        for position in start.legal_moves() {
+            grid.apply(position);
+            assert_ne!(grid, start);
+            grid.undo(position);
+            assert_eq!(grid, start);
+        }
+    }
+
+    #[test]
+    fn status() {
+        assert!(played(&[]).status() == Status::Ongoing);
+        let column = played(&[pos(0, 0), pos(1, 0), pos(0, 1), pos(1, 1), pos(0, 2)]);
+        assert!(column.status() == Status::Won(Player::Red));
+        let draw = played(&[
+            pos(0, 0),
+            pos(1, 0),
+            pos(2, 0),
+            pos(1, 1),
+            pos(0, 1),
+            pos(0, 2),
+            pos(1, 2),
+            pos(2, 1),
+            pos(2, 2),
+        ]);
+        assert!(draw.status() == Status::Draw);
+        assert_eq!(draw.legal_moves().count(), 0);
+    }
+
+    #[test]
+    fn takes_a_win() {
+        // Red can finish the top row, or has to block blue's middle row.
+        let grid = played(&[pos(0, 0), pos(0, 1), pos(1, 0), pos(1, 1)]);
+        assert_eq!(best_move(grid, 4), Some(pos(2, 0)));
+    }
+
+    #[test]
+    fn blocks_a_win() {
+        // Blue has to block red's top row.
+        let grid = played(&[pos(0, 0), pos(1, 1), pos(1, 0)]);
+        assert_eq!(best_move(grid, 4), Some(pos(2, 0)));
+    }
+
+    #[test]
+    fn never_loses() {
+        for first in [Player::Red, Player::Blue] {
+            assert_eq!(losses(&mut Grid::<3>::new(first), Player::Blue), 0);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! What games played in turns share: showing who goes first, the moves
+//! played so far for taking back, and which side the computer plays.
+
+use crate::{
+    animation::{Animation, Effect, Playback},
+    board::Board,
+    display::DisplayPixel,
+    game::Tick,
+    history::History,
+    player::Player,
+    rules::{Rules, Status},
+    time::{Duration, Instant, Timer},
+    transition::Transition,
+};
+
+/// Moves that can be taken back, enough for a whole 4x4 game.
+const UNDO_DEPTH: usize = 16;
+/// How long whoever goes first is shown before the board.
+const PRESENT: Duration = Duration::from_secs(1);
+/// How long the computer's move pulses for.
+const PULSE: Duration = Duration::from_millis(800);
+
+/// A game's position, the state it is in, and how it got there.
+pub struct Turns<R: Rules, S, const W: usize, const H: usize> {
+    pub grid: R,
+    pub state: S,
+    history: History<R::Move, UNDO_DEPTH>,
+    /// The side the computer plays, if any.
+    computer: Option<Player>,
+    /// How long the game has been in `state`.
+    timer: Timer,
+    pub animation: Option<Animation<W, H>>,
+    /// Requested by a change of state, and passed on with the next frame.
+    pub transition: Option<Transition>,
+}
+
+impl<R, S, const W: usize, const H: usize> Turns<R, S, W, H>
+where
+    R: Rules + Copy,
+    S: Default,
+{
+    /// A game starting from `grid` in the default state, with `computer`
+    /// playing one side or `None` for two players.
+    pub fn new(grid: R, computer: Option<Player>) -> Self {
+        Self {
+            grid,
+            state: S::default(),
+            history: History::new(),
+            computer,
+            timer: Timer::new(),
+            animation: None,
+            transition: None,
+        }
+    }
+
+    pub fn enter(&mut self, state: S) {
+        self.state = state;
+        self.timer.restart();
+    }
+
+    pub fn computers_turn(&self) -> bool {
+        self.computer == Some(self.grid.side_to_move())
+    }
+
+    /// Fills the matrix with whoever goes first until they have been shown
+    /// long enough, then enters `next` with `transition`.
+    pub fn present(
+        &mut self,
+        now: Instant,
+        next: S,
+        transition: Transition,
+    ) -> Board<DisplayPixel, W, H> {
+        if self.timer.after(now, PRESENT) {
+            self.enter(next);
+            self.transition = Some(transition);
+        }
+        [[DisplayPixel::from(self.grid.side_to_move()); W]; H]
+    }
+
+    /// Plays `mv` and enters `state` to wait for the next one.
+    pub fn play(&mut self, mv: R::Move, state: S) {
+        self.grid.apply(mv);
+        self.history.push(mv);
+        self.enter(state);
+        self.animation = None;
+    }
+
+    /// Plays the computer's `mv` as [`Self::play`] does, pulsing `cells`
+    /// once so the player sees where it went.
+    pub fn play_computer(&mut self, mv: R::Move, state: S, cells: Board<bool, W, H>) {
+        self.play(mv, state);
+        self.animation = Some(Animation::new(
+            Effect::Pulse { cells },
+            PULSE,
+            Playback::Once,
+        ));
+    }
+
+    /// Takes back the last move, even once the game is over, and enters
+    /// `state` with `transition`. Against the computer, moves are taken back
+    /// until it is the player's turn, or the computer would just play its
+    /// move again.
+    pub fn undo(&mut self, state: S, transition: Transition) {
+        let mut undone = false;
+        while let Some(mv) = self.history.pop() {
+            self.grid.undo(mv);
+            undone = true;
+            if !self.computers_turn() {
+                break;
+            }
+        }
+        if undone {
+            self.enter(state);
+            self.animation = None;
+            self.transition = Some(transition);
+        }
+    }
+
+    /// The position to find the computer a move from, if it is the computer's
+    /// turn while `waiting` for a move.
+    pub fn think(&self, waiting: bool) -> Option<R> {
+        let thinking = waiting && self.computers_turn() && self.grid.status() == Status::Ongoing;
+        thinking.then_some(self.grid)
+    }
+
+    /// Draws the animation over `frame`, easing it in if a change of state
+    /// asked for a transition.
+    pub fn tick(&mut self, frame: Board<DisplayPixel, W, H>, now: Instant) -> Tick<W, H> {
+        let frame = match &mut self.animation {
+            Some(animation) => animation.draw(frame, now),
+            None => frame,
+        };
+        match self.transition.take() {
+            Some(transition) => Tick::Transition(frame, transition),
+            None => Tick::Frame(frame),
+        }
+    }
+}